@@ -0,0 +1,310 @@
+use crate::{
+    configuration::COPY_SCRATCHPAD_DELAY, BusObserver, Configuration, Driver, Ds18b20Error, Error,
+    Speed, StrongPullUp,
+};
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+use embedded_hal_async::delay::DelayNs;
+
+/// 1-Wire bus
+///
+/// Asynchronous counterpart of [`crate::OneWireBus`]: the bus operations are
+/// awaited, the ROM and memory commands of this module are written against
+/// this trait.
+#[allow(async_fn_in_trait)]
+pub trait OneWireBus {
+    /// Error of the underlying peripheral.
+    type Error;
+
+    /// Initialization.
+    ///
+    /// Sends the reset pulse and returns `true` if a presence pulse was
+    /// detected.
+    async fn initialization(&mut self) -> Result<bool, Error<Self::Error>>;
+
+    /// Read a bit from the 1-Wire bus.
+    async fn read_bit(&mut self) -> Result<bool, Error<Self::Error>>;
+
+    /// Send a 1-Wire write bit.
+    async fn write_bit(&mut self, bit: bool) -> Result<(), Error<Self::Error>>;
+
+    /// Waits for the given time (ns).
+    async fn delay(&mut self, ns: u32);
+
+    /// See [`crate::OneWireBus::parasite_power`].
+    fn parasite_power(&self) -> bool;
+
+    /// See [`crate::OneWireBus::set_parasite_power`].
+    fn set_parasite_power(&mut self, parasite_power: bool);
+
+    /// Read 1-Wire data byte.
+    async fn read_byte(&mut self) -> Result<u8, Error<Self::Error>> {
+        read_byte(self).await
+    }
+
+    async fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        for byte in bytes {
+            *byte = self.read_byte().await?;
+        }
+        Ok(())
+    }
+
+    /// Write 1-Wire data byte.
+    async fn write_byte(&mut self, byte: u8) -> Result<(), Error<Self::Error>> {
+        write_byte(self, byte).await
+    }
+
+    async fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<Self::Error>> {
+        for byte in bytes {
+            self.write_byte(*byte).await?;
+        }
+        Ok(())
+    }
+
+    /// Write a ROM or function command.
+    async fn write_command(&mut self, command: u8) -> Result<(), Error<Self::Error>> {
+        self.write_byte(command).await
+    }
+
+    /// See [`crate::OneWireBus::report`].
+    fn report(&mut self, error: Error<Self::Error>) -> Error<Self::Error> {
+        error
+    }
+
+    /// See [`crate::OneWireBus::report_retry`].
+    fn report_retry(&mut self, _error: &Error<Self::Error>) {}
+
+    /// See [`crate::OneWireBus::write_command_with_pull_up`].
+    async fn write_command_with_pull_up(
+        &mut self,
+        command: u8,
+        ns: u32,
+    ) -> Result<(), Error<Self::Error>> {
+        self.write_command(command).await?;
+        self.delay(ns).await;
+        Ok(())
+    }
+
+    /// See [`crate::OneWireBus::copy_scratchpad_delay`].
+    fn copy_scratchpad_delay(&self) -> u32 {
+        COPY_SCRATCHPAD_DELAY
+    }
+
+    /// Returns the speed of the following operations.
+    fn speed(&self) -> Speed {
+        Speed::Standard
+    }
+
+    /// See [`crate::OneWireBus::supports_speed`].
+    fn supports_speed(&self, speed: Speed) -> bool {
+        speed == Speed::Standard
+    }
+
+    /// See [`crate::OneWireBus::set_speed`].
+    fn set_speed(&mut self, speed: Speed) -> Result<(), Error<Self::Error>> {
+        if !self.supports_speed(speed) {
+            Err(Ds18b20Error::UnsupportedSpeed { speed })?;
+        }
+        Ok(())
+    }
+
+    /// Initialization at standard speed.
+    ///
+    /// The standard reset pulse switches all devices back to standard speed.
+    async fn standard_speed_initialization(&mut self) -> Result<bool, Error<Self::Error>> {
+        self.set_speed(Speed::Standard)?;
+        self.initialization().await
+    }
+
+    /// See [`crate::OneWireBus::triplet`].
+    async fn triplet(&mut self, direction: bool) -> Result<(bool, bool, bool), Error<Self::Error>> {
+        let bit = self.read_bit().await?;
+        let complement = self.read_bit().await?;
+        let direction = match (bit, complement) {
+            (false, false) => direction,
+            (bit, _) => bit,
+        };
+        self.write_bit(direction).await?;
+        Ok((bit, complement, direction))
+    }
+
+    /// See [`crate::OneWireBus::triplets`].
+    async fn triplets(&mut self, directions: u64) -> Result<(u64, u64), Error<Self::Error>> {
+        let mut bits = 0;
+        let mut complements = 0;
+        for index in 0..u64::BITS {
+            let (bit, complement, _) = self.triplet(directions >> index & 0x01 == 0x01).await?;
+            bits |= (bit as u64) << index;
+            complements |= (complement as u64) << index;
+        }
+        Ok((bits, complements))
+    }
+}
+
+impl<T, U, V, W> OneWireBus for Driver<T, U, V, W>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: embedded_hal::delay::DelayNs + DelayNs,
+    V: StrongPullUp<T::Error>,
+    W: BusObserver,
+{
+    type Error = T::Error;
+
+    /// Only the reset pulse is awaited, the presence pulse is sampled with
+    /// the blocking delay.
+    async fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+        let presence = match initialization(self).await {
+            Ok(presence) => presence,
+            Err(error) => return Err(OneWireBus::report(self, error)),
+        };
+        self.observer.reset(presence);
+        Ok(presence)
+    }
+
+    async fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+        self.read_slot()
+            .map_err(|error| OneWireBus::report(self, error))
+    }
+
+    async fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+        self.write_slot(bit)
+            .map_err(|error| OneWireBus::report(self, error))
+    }
+
+    async fn read_byte(&mut self) -> Result<u8, Error<T::Error>> {
+        let byte = read_byte(self).await?;
+        self.observer.read(byte);
+        Ok(byte)
+    }
+
+    async fn write_byte(&mut self, byte: u8) -> Result<(), Error<T::Error>> {
+        write_byte(self, byte).await?;
+        self.observer.write(byte);
+        Ok(())
+    }
+
+    async fn write_command(&mut self, command: u8) -> Result<(), Error<T::Error>> {
+        write_byte(self, command).await?;
+        self.observer.command(command);
+        Ok(())
+    }
+
+    fn report(&mut self, error: Error<T::Error>) -> Error<T::Error> {
+        self.observer.error(&error);
+        error
+    }
+
+    fn report_retry(&mut self, error: &Error<T::Error>) {
+        self.observer.retry(error);
+    }
+
+    async fn delay(&mut self, ns: u32) {
+        DelayNs::delay_ns(&mut self.delay, ns).await;
+    }
+
+    /// Enables the strong pull-up right after the low phase of the last bit
+    /// if parasite-powered devices are on the bus, like the blocking
+    /// [`crate::OneWireBus`] implementation. Only the wait is awaited.
+    async fn write_command_with_pull_up(
+        &mut self,
+        command: u8,
+        ns: u32,
+    ) -> Result<(), Error<T::Error>> {
+        if !self.parasite_power {
+            OneWireBus::write_command(self, command).await?;
+            OneWireBus::delay(self, ns).await;
+            return Ok(());
+        }
+        for index in 0..u8::BITS - 1 {
+            OneWireBus::write_bit(self, command >> index & 0x01 == 0x01).await?;
+        }
+        if let Err(error) = write_slot_with_pull_up(self, command & 0x80 == 0x80, ns).await {
+            return Err(OneWireBus::report(self, error));
+        }
+        self.observer.command(command);
+        Ok(())
+    }
+
+    fn copy_scratchpad_delay(&self) -> u32 {
+        self.configuration().copy_scratchpad_delay
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+
+    fn speed(&self) -> Speed {
+        Driver::speed(self)
+    }
+
+    fn supports_speed(&self, _speed: Speed) -> bool {
+        true
+    }
+
+    /// Selects the timing configuration of the speed.
+    fn set_speed(&mut self, speed: Speed) -> Result<(), Error<T::Error>> {
+        self.speed = speed;
+        Ok(())
+    }
+}
+
+/// See [`crate::OneWireBus::initialization`].
+async fn initialization<T, U, V, W>(
+    driver: &mut Driver<T, U, V, W>,
+) -> Result<bool, Error<T::Error>>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: embedded_hal::delay::DelayNs + DelayNs,
+{
+    let h = driver.configuration().h;
+    driver.wait_for_high()?;
+    driver.set_low()?;
+    DelayNs::delay_ns(&mut driver.delay, h).await;
+    driver.presence_detect()
+}
+
+/// Write time slot, then supply parasite-powered devices through the strong
+/// pull-up for the given time (ns), which is awaited.
+async fn write_slot_with_pull_up<T, U, V, W>(
+    driver: &mut Driver<T, U, V, W>,
+    bit: bool,
+    ns: u32,
+) -> Result<(), Error<T::Error>>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: embedded_hal::delay::DelayNs + DelayNs,
+    V: StrongPullUp<T::Error>,
+{
+    let Configuration { a, c, .. } = *driver.configuration();
+    driver.set_low()?;
+    driver.delay_ns(if bit { a } else { c });
+    driver.set_high()?;
+    driver.pull_up.enable().map_err(Error::Pin)?;
+    DelayNs::delay_ns(&mut driver.delay, ns).await;
+    driver.pull_up.disable().map_err(Error::Pin)
+}
+
+async fn read_byte<B: OneWireBus + ?Sized>(bus: &mut B) -> Result<u8, Error<B::Error>> {
+    let mut byte = 0;
+    for _ in 0..u8::BITS {
+        byte >>= 1;
+        if bus.read_bit().await? {
+            byte |= 0x80;
+        }
+    }
+    Ok(byte)
+}
+
+async fn write_byte<B: OneWireBus + ?Sized>(
+    bus: &mut B,
+    mut byte: u8,
+) -> Result<(), Error<B::Error>> {
+    for _ in 0..u8::BITS {
+        bus.write_bit(byte & 0x01 == 0x01).await?;
+        byte >>= 1;
+    }
+    Ok(())
+}
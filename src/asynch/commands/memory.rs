@@ -0,0 +1,133 @@
+use crate::{
+    asynch::{OneWireBus, RomCommands},
+    commands::memory::{
+        PowerSupply, COMMAND_MEMORY_CONVERT, COMMAND_MEMORY_POWER_SUPPLY_READ,
+        COMMAND_MEMORY_RECALL, COMMAND_MEMORY_SCRATCHPAD_COPY, COMMAND_MEMORY_SCRATCHPAD_READ,
+        COMMAND_MEMORY_SCRATCHPAD_WRITE, READ_SLOT_DURATION_MICROS,
+    },
+    error::{Ds18b20Error, Error},
+    scratchpad::{Resolution, Scratchpad},
+};
+
+/// Memory commands
+///
+/// Asynchronous counterpart of [`crate::MemoryCommands`].
+#[allow(async_fn_in_trait)]
+pub trait MemoryCommands: OneWireBus {
+    /// Convert temperature command
+    ///
+    /// See [`crate::MemoryCommands::convert_temperature`].
+    async fn convert_temperature(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Signals the mode of DS18B20 power supply to the master.
+    ///
+    /// See [`crate::MemoryCommands::read_power_supply`].
+    async fn read_power_supply(&mut self) -> Result<PowerSupply, Error<Self::Error>>;
+
+    /// Reads the power supply mode of all devices simultaneously.
+    ///
+    /// See [`crate::MemoryCommands::read_power_supply_all`].
+    async fn read_power_supply_all(&mut self) -> Result<PowerSupply, Error<Self::Error>>;
+
+    /// Recalls values stored in nonvolatile memory (EEPROM) into scratchpad
+    /// (temperature triggers).
+    async fn recall_eeprom(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Copies scratchpad into nonvolatile memory (EEPROM) (addresses 2 through
+    /// 4 only) and waits for the copy to finish.
+    async fn copy_scratchpad(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Reads bytes from scratchpad and reads CRC byte.
+    async fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<Self::Error>>;
+
+    /// Writes bytes into scratchpad at addresses 2 through 4 (TH and TL
+    /// temperature triggers and config).
+    async fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<Self::Error>>;
+
+    /// Begins a temperature conversion on all devices simultaneously.
+    ///
+    /// See [`crate::MemoryCommands::start_conversion_all`].
+    async fn start_conversion_all(&mut self) -> Result<(), Error<Self::Error>>;
+}
+
+impl<B: OneWireBus> MemoryCommands for B {
+    async fn convert_temperature(&mut self) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_CONVERT).await?;
+        Ok(())
+    }
+
+    async fn read_power_supply(&mut self) -> Result<PowerSupply, Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_POWER_SUPPLY_READ).await?;
+        if self.read_bit().await? {
+            return Ok(PowerSupply::External);
+        }
+        self.set_parasite_power(true);
+        Ok(PowerSupply::Parasite)
+    }
+
+    async fn read_power_supply_all(&mut self) -> Result<PowerSupply, Error<B::Error>> {
+        skip(self).await?;
+        let power_supply = self.read_power_supply().await?;
+        self.set_parasite_power(power_supply == PowerSupply::Parasite);
+        Ok(power_supply)
+    }
+
+    async fn recall_eeprom(&mut self) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_RECALL).await?;
+        // wait for the recall to finish (up to 10ms)
+        let max_retries = (10000 / READ_SLOT_DURATION_MICROS) + 1;
+        for _ in 0..max_retries {
+            if self.read_bit().await? {
+                return Ok(());
+            }
+        }
+        Err(self.report(Ds18b20Error::Timeout.into()))
+    }
+
+    async fn copy_scratchpad(&mut self) -> Result<(), Error<B::Error>> {
+        // wait for the write to complete
+        let ns = self.copy_scratchpad_delay();
+        self.write_command_with_pull_up(COMMAND_MEMORY_SCRATCHPAD_COPY, ns)
+            .await
+    }
+
+    async fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_SCRATCHPAD_READ).await?;
+        let mut bytes = [0; 9];
+        self.read_bytes(&mut bytes).await?;
+        bytes
+            .try_into()
+            .map_err(|error| self.report(Error::from(error)))
+    }
+
+    async fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_SCRATCHPAD_WRITE).await?;
+        self.write_byte(scratchpad.triggers.high as _).await?;
+        self.write_byte(scratchpad.triggers.low as _).await?;
+        self.write_byte(scratchpad.configuration_register.into())
+            .await?;
+        Ok(())
+    }
+
+    async fn start_conversion_all(&mut self) -> Result<(), Error<B::Error>> {
+        skip(self).await?;
+        if self.parasite_power() {
+            self.write_command_with_pull_up(
+                COMMAND_MEMORY_CONVERT,
+                Resolution::Twelve.conversion_time(),
+            )
+            .await
+        } else {
+            self.convert_temperature().await
+        }
+    }
+}
+
+/// Sends the initialization sequence and the skip ROM command, after which
+/// all devices wait for a memory function command.
+async fn skip<B: OneWireBus>(bus: &mut B) -> Result<(), Error<B::Error>> {
+    if !bus.initialization().await? {
+        return Err(bus.report(Ds18b20Error::NoAttachedDevices.into()));
+    }
+    bus.skip_rom().await
+}
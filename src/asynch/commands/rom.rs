@@ -0,0 +1,156 @@
+use crate::{
+    asynch::OneWireBus,
+    commands::rom::{
+        Search, SearchState, COMMAND_ALARM_SEARCH, COMMAND_ROM_MATCH, COMMAND_ROM_OVERDRIVE_MATCH,
+        COMMAND_ROM_OVERDRIVE_SKIP, COMMAND_ROM_READ, COMMAND_ROM_SEARCH, COMMAND_ROM_SKIP,
+    },
+    error::Ds18b20Error,
+    Error, Rom, Speed,
+};
+
+/// Rom commands
+///
+/// Asynchronous counterpart of [`crate::RomCommands`].
+#[allow(async_fn_in_trait)]
+pub trait RomCommands: OneWireBus {
+    /// Read ROM command
+    ///
+    /// See [`crate::RomCommands::read_rom`].
+    async fn read_rom(&mut self) -> Result<Rom, Error<Self::Error>>;
+
+    /// Match ROM command
+    ///
+    /// See [`crate::RomCommands::match_rom`].
+    async fn match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>>;
+
+    /// Skip ROM command
+    ///
+    /// See [`crate::RomCommands::skip_rom`].
+    async fn skip_rom(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Overdrive match ROM command
+    ///
+    /// See [`crate::RomCommands::overdrive_match_rom`].
+    async fn overdrive_match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>>;
+
+    /// Overdrive skip ROM command
+    ///
+    /// See [`crate::RomCommands::overdrive_skip_rom`].
+    async fn overdrive_skip_rom(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Search ROM command
+    ///
+    /// See [`crate::RomCommands::search_rom`].
+    async fn search_rom(
+        &mut self,
+        state: &mut SearchState,
+    ) -> Result<Option<Rom>, Error<Self::Error>>;
+
+    /// Search alarm command
+    ///
+    /// See [`crate::RomCommands::search_alarm`].
+    async fn search_alarm(
+        &mut self,
+        state: &mut SearchState,
+    ) -> Result<Option<Rom>, Error<Self::Error>>;
+}
+
+impl<B: OneWireBus> RomCommands for B {
+    async fn read_rom(&mut self) -> Result<Rom, Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_READ).await?;
+        let mut bytes = [0; 8];
+        self.read_bytes(&mut bytes).await?;
+        bytes
+            .try_into()
+            .map_err(|error| self.report(Error::from(error)))
+    }
+
+    async fn match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_MATCH).await?;
+        let bytes: [u8; 8] = rom.into();
+        self.write_bytes(&bytes).await?;
+        Ok(())
+    }
+
+    async fn skip_rom(&mut self) -> Result<(), Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_SKIP).await?;
+        Ok(())
+    }
+
+    async fn overdrive_match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>> {
+        check_overdrive(self)?;
+        self.write_command(COMMAND_ROM_OVERDRIVE_MATCH).await?;
+        self.set_speed(Speed::Overdrive)?;
+        let bytes: [u8; 8] = rom.into();
+        self.write_bytes(&bytes).await?;
+        Ok(())
+    }
+
+    async fn overdrive_skip_rom(&mut self) -> Result<(), Error<Self::Error>> {
+        check_overdrive(self)?;
+        self.write_command(COMMAND_ROM_OVERDRIVE_SKIP).await?;
+        self.set_speed(Speed::Overdrive)
+    }
+
+    async fn search_rom(
+        &mut self,
+        state: &mut SearchState,
+    ) -> Result<Option<Rom>, Error<Self::Error>> {
+        search(self, COMMAND_ROM_SEARCH, state).await
+    }
+
+    async fn search_alarm(
+        &mut self,
+        state: &mut SearchState,
+    ) -> Result<Option<Rom>, Error<Self::Error>> {
+        search(self, COMMAND_ALARM_SEARCH, state).await
+    }
+}
+
+/// See [`crate::RomCommands::overdrive_skip_rom`].
+fn check_overdrive<B: OneWireBus>(bus: &mut B) -> Result<(), Error<B::Error>> {
+    if !bus.supports_speed(Speed::Overdrive) {
+        let speed = Speed::Overdrive;
+        return Err(bus.report(Ds18b20Error::UnsupportedSpeed { speed }.into()));
+    }
+    Ok(())
+}
+
+/// Search for the next device after the one recorded in the state.
+///
+/// The same algorithm as the blocking search, with the triplets awaited.
+async fn search<B: OneWireBus>(
+    bus: &mut B,
+    command: u8,
+    state: &mut SearchState,
+) -> Result<Option<Rom>, Error<B::Error>> {
+    if state.is_finished() {
+        return Ok(None);
+    }
+    // All transactions on the 1-Wire bus begin with an initialization
+    // sequence.
+    if !bus.initialization().await? {
+        return Err(bus.report(Ds18b20Error::NoAttachedDevices.into()));
+    }
+    bus.write_command(command).await?;
+    let mut search = Search::new(state);
+    let directions = (0..u64::BITS).fold(0, |directions, index| {
+        directions | (search.direction(index) as u64) << index
+    });
+    let (bits, complements) = bus.triplets(directions).await?;
+    for index in 0..u64::BITS {
+        let response = (
+            bits >> index & 0x01 == 0x01,
+            complements >> index & 0x01 == 0x01,
+        );
+        match search.step(index, response) {
+            Ok(Some(_)) => {}
+            Ok(None) => return Ok(None),
+            Err(error) => return Err(bus.report(error.into())),
+        }
+    }
+    search
+        .finish()
+        .map(Some)
+        .map_err(|error| bus.report(error.into()))
+}
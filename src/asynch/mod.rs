@@ -0,0 +1,134 @@
+//! Asynchronous implementation of the 1-Wire protocol.
+//!
+//! The [`Driver`](crate::Driver) implements the asynchronous [`OneWireBus`]
+//! when its delay implements both the blocking and the asynchronous
+//! [`DelayNs`](embedded_hal_async::delay::DelayNs). The long waits (reset
+//! pulse, temperature conversion, EEPROM copy) are awaited so they don't
+//! block the executor. The time slots and the presence sampling keep the
+//! blocking delay: the executor could otherwise run other tasks in the middle
+//! of a slot and stretch its timing past the limits of the devices.
+//!
+//! The ROM and memory commands are the asynchronous counterparts of
+//! [`crate::RomCommands`] and [`crate::MemoryCommands`], with the same
+//! [`Rom`](crate::Rom), [`Scratchpad`](crate::Scratchpad) and
+//! [`SearchState`](crate::SearchState) types.
+
+pub use self::{
+    bus::OneWireBus,
+    commands::{memory::MemoryCommands, rom::RomCommands},
+};
+
+mod bus;
+mod commands;
+
+#[cfg(all(test, feature = "simulator"))]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::{
+        scratchpad::Resolution,
+        simulator::{
+            testing::{bus, device},
+            Delay, Pin,
+        },
+        Driver, PowerSupply, Rom, SearchState, StrongPullUp, Temperature,
+    };
+    use core::{
+        cell::Cell,
+        convert::Infallible,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+    use std::vec::Vec;
+
+    /// Polls the future to completion, the simulated delays are ready at once.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn commands() {
+        let devices = [device(0x6F), device(0x01)]
+            .map(|device| device.with_temperature(Temperature::from_celsius(21)));
+        let bus = bus(&devices, Default::default());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        block_on(async {
+            let mut state = SearchState::default();
+            let mut roms = Vec::new();
+            while let Some(rom) = driver.search_rom(&mut state).await.unwrap() {
+                roms.push(rom);
+            }
+            let mut expected: Vec<Rom> = devices.iter().map(|device| *device.rom()).collect();
+            expected.sort_by_key(|&rom| u64::from(rom).reverse_bits());
+            assert_eq!(expected, roms);
+
+            driver.start_conversion_all().await.unwrap();
+            driver.delay(Resolution::Twelve.conversion_time()).await;
+            for rom in roms {
+                assert_eq!(Ok(true), driver.initialization().await);
+                driver.match_rom(rom).await.unwrap();
+                let scratchpad = driver.read_scratchpad().await.unwrap();
+                assert_eq!(
+                    Ok(Temperature::from_celsius(21)),
+                    scratchpad.measured_temperature()
+                );
+            }
+            assert_eq!(Ok(true), driver.initialization().await);
+            driver.skip_rom().await.unwrap();
+            assert_eq!(Ok(PowerSupply::External), driver.read_power_supply().await);
+        });
+    }
+
+    /// Counts the enables and disables of the strong pull-up.
+    struct PullUp<'a>(&'a Cell<(u32, u32)>);
+
+    impl StrongPullUp<Infallible> for PullUp<'_> {
+        fn enable(&mut self) -> Result<(), Infallible> {
+            let (enables, disables) = self.0.get();
+            self.0.set((enables + 1, disables));
+            Ok(())
+        }
+
+        fn disable(&mut self) -> Result<(), Infallible> {
+            let (enables, disables) = self.0.get();
+            self.0.set((enables, disables + 1));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn strong_pull_up() {
+        let parasite = device(0x6F).with_parasite_power();
+        let bus = bus(&[parasite], Default::default());
+        let pull_up = Cell::new((0, 0));
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus))
+            .unwrap()
+            .with_strong_pull_up(PullUp(&pull_up));
+        block_on(async {
+            assert_eq!(
+                Ok(PowerSupply::Parasite),
+                driver.read_power_supply_all().await
+            );
+            assert!(driver.parasite_power());
+            let start = bus.borrow().now();
+            driver.start_conversion_all().await.unwrap();
+            assert!(bus.borrow().now() - start >= Resolution::Twelve.conversion_time() as u64);
+        });
+        assert_eq!((1, 1), pull_up.get());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    configuration::COPY_SCRATCHPAD_DELAY, BusObserver, Driver, Ds18b20Error, Error, Speed,
+    StrongPullUp,
+};
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType, InputPin, OutputPin},
+};
+
+/// 1-Wire bus
+///
+/// The basic operations of a 1-Wire master: `Reset`, `Write` bit and `Read`
+/// bit. The ROM and memory commands are written against this trait, so any
+/// master (bit-banged GPIO, UART, I²C bridge, ...) can be used with them.
+///
+/// The byte operations, the strong pull-up and the triplet have default
+/// implementations in terms of the bit operations, a master may provide faster
+/// ones.
+pub trait OneWireBus {
+    /// Error of the underlying peripheral.
+    type Error;
+
+    /// Initialization.
+    ///
+    /// Sends the reset pulse and returns `true` if a presence pulse was
+    /// detected.
+    fn initialization(&mut self) -> Result<bool, Error<Self::Error>>;
+
+    /// Read a bit from the 1-Wire bus.
+    fn read_bit(&mut self) -> Result<bool, Error<Self::Error>>;
+
+    /// Send a 1-Wire write bit.
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<Self::Error>>;
+
+    /// Waits for the given time (ns).
+    fn delay(&mut self, ns: u32);
+
+    /// Returns `true` if parasite-powered devices are on the bus, so the
+    /// temperature conversions have to be followed by the strong pull-up.
+    fn parasite_power(&self) -> bool;
+
+    /// Records whether parasite-powered devices are on the bus, see
+    /// [`MemoryCommands::read_power_supply`](crate::MemoryCommands::read_power_supply).
+    fn set_parasite_power(&mut self, parasite_power: bool);
+
+    /// Read 1-Wire data byte.
+    fn read_byte(&mut self) -> Result<u8, Error<Self::Error>> {
+        read_byte(self)
+    }
+
+    fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        for byte in bytes {
+            *byte = self.read_byte()?;
+        }
+        Ok(())
+    }
+
+    /// Write 1-Wire data byte.
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error<Self::Error>> {
+        write_byte(self, byte)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<Self::Error>> {
+        for byte in bytes {
+            self.write_byte(*byte)?;
+        }
+        Ok(())
+    }
+
+    /// Write a ROM or function command.
+    fn write_command(&mut self, command: u8) -> Result<(), Error<Self::Error>> {
+        self.write_byte(command)
+    }
+
+    /// Reports an error produced on the bus (by the master or the devices),
+    /// before it is returned.
+    fn report(&mut self, error: Error<Self::Error>) -> Error<Self::Error> {
+        error
+    }
+
+    /// Reports that the operation failing with the error is retried.
+    fn report_retry(&mut self, _error: &Error<Self::Error>) {}
+
+    /// Writes a function command followed by the strong pull-up.
+    ///
+    /// Waits for the given time (ns) for the operation started by the
+    /// command (temperature conversion, EEPROM write) to complete. If
+    /// parasite-powered devices are on the bus, the strong pull-up supplies
+    /// them meanwhile: it has to be enabled within 10 µs of the last bit, so
+    /// the master arms it before writing the command. A master without a
+    /// strong pull-up only waits.
+    fn write_command_with_pull_up(
+        &mut self,
+        command: u8,
+        ns: u32,
+    ) -> Result<(), Error<Self::Error>> {
+        self.write_command(command)?;
+        self.delay(ns);
+        Ok(())
+    }
+
+    /// Returns the time (ns) to wait for the EEPROM write of the copy
+    /// scratchpad command.
+    fn copy_scratchpad_delay(&self) -> u32 {
+        COPY_SCRATCHPAD_DELAY
+    }
+
+    /// Returns the speed of the following operations.
+    fn speed(&self) -> Speed {
+        Speed::Standard
+    }
+
+    /// Returns `true` if the master can switch to the speed.
+    ///
+    /// A master without overdrive supports the standard speed only.
+    fn supports_speed(&self, speed: Speed) -> bool {
+        speed == Speed::Standard
+    }
+
+    /// Switches the master to the speed, the devices are switched by the
+    /// overdrive ROM commands.
+    fn set_speed(&mut self, speed: Speed) -> Result<(), Error<Self::Error>> {
+        if !self.supports_speed(speed) {
+            Err(Ds18b20Error::UnsupportedSpeed { speed })?;
+        }
+        Ok(())
+    }
+
+    /// Initialization at standard speed.
+    ///
+    /// The standard reset pulse switches all devices back to standard speed.
+    fn standard_speed_initialization(&mut self) -> Result<bool, Error<Self::Error>> {
+        self.set_speed(Speed::Standard)?;
+        self.initialization()
+    }
+
+    /// Triplet.
+    ///
+    /// One step of the search: reads a bit and its complement, then writes
+    /// the read bit if they differ or `direction` if they are both `0`.
+    /// Returns the two read bits and the written one.
+    fn triplet(&mut self, direction: bool) -> Result<(bool, bool, bool), Error<Self::Error>> {
+        let bit = self.read_bit()?;
+        let complement = self.read_bit()?;
+        let direction = match (bit, complement) {
+            (false, false) => direction,
+            (bit, _) => bit,
+        };
+        self.write_bit(direction)?;
+        Ok((bit, complement, direction))
+    }
+
+    /// Triplets of a whole search pass.
+    ///
+    /// Runs the 64 triplets of a search, `directions` holds the direction for
+    /// each bit position. Returns the read bits and their complements. A
+    /// master with a search accelerator may do it at once.
+    fn triplets(&mut self, directions: u64) -> Result<(u64, u64), Error<Self::Error>> {
+        let mut bits = 0;
+        let mut complements = 0;
+        for index in 0..u64::BITS {
+            let (bit, complement, _) = self.triplet(directions >> index & 0x01 == 0x01)?;
+            bits |= (bit as u64) << index;
+            complements |= (complement as u64) << index;
+        }
+        Ok((bits, complements))
+    }
+}
+
+impl<T, U, V, W> OneWireBus for Driver<T, U, V, W>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: DelayNs,
+    V: StrongPullUp<T::Error>,
+    W: BusObserver,
+{
+    type Error = T::Error;
+
+    fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+        let presence = Driver::reset_pulse(self).map_err(|error| self.report(error))?;
+        self.observer.reset(presence);
+        Ok(presence)
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+        Driver::read_slot(self).map_err(|error| self.report(error))
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+        Driver::write_slot(self, bit).map_err(|error| self.report(error))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error<T::Error>> {
+        let byte = read_byte(self)?;
+        self.observer.read(byte);
+        Ok(byte)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error<T::Error>> {
+        write_byte(self, byte)?;
+        self.observer.write(byte);
+        Ok(())
+    }
+
+    fn write_command(&mut self, command: u8) -> Result<(), Error<T::Error>> {
+        write_byte(self, command)?;
+        self.observer.command(command);
+        Ok(())
+    }
+
+    fn report(&mut self, error: Error<T::Error>) -> Error<T::Error> {
+        self.observer.error(&error);
+        error
+    }
+
+    fn report_retry(&mut self, error: &Error<T::Error>) {
+        self.observer.retry(error);
+    }
+
+    fn delay(&mut self, ns: u32) {
+        Driver::delay_ns(self, ns)
+    }
+
+    /// Enables the strong pull-up right after the low phase of the last bit
+    /// if parasite-powered devices are on the bus, instead of after its
+    /// recovery time (D, 10 µs).
+    fn write_command_with_pull_up(&mut self, command: u8, ns: u32) -> Result<(), Error<T::Error>> {
+        if !self.parasite_power {
+            self.write_command(command)?;
+            Driver::delay_ns(self, ns);
+            return Ok(());
+        }
+        for index in 0..u8::BITS - 1 {
+            self.write_bit(command >> index & 0x01 == 0x01)?;
+        }
+        Driver::write_slot_with_pull_up(self, command & 0x80 == 0x80, ns)
+            .map_err(|error| self.report(error))?;
+        self.observer.command(command);
+        Ok(())
+    }
+
+    fn copy_scratchpad_delay(&self) -> u32 {
+        self.configuration().copy_scratchpad_delay
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+
+    fn speed(&self) -> Speed {
+        Driver::speed(self)
+    }
+
+    fn supports_speed(&self, _speed: Speed) -> bool {
+        true
+    }
+
+    /// Selects the timing configuration of the speed.
+    fn set_speed(&mut self, speed: Speed) -> Result<(), Error<T::Error>> {
+        self.speed = speed;
+        Ok(())
+    }
+}
+
+fn read_byte<B: OneWireBus + ?Sized>(bus: &mut B) -> Result<u8, Error<B::Error>> {
+    let mut byte = 0;
+    for _ in 0..u8::BITS {
+        byte >>= 1;
+        if bus.read_bit()? {
+            byte |= 0x80;
+        }
+    }
+    Ok(byte)
+}
+
+fn write_byte<B: OneWireBus + ?Sized>(bus: &mut B, mut byte: u8) -> Result<(), Error<B::Error>> {
+    for _ in 0..u8::BITS {
+        bus.write_bit(byte & 0x01 == 0x01)?;
+        byte >>= 1;
+    }
+    Ok(())
+}
@@ -1,11 +1,7 @@
 use crate::{
     error::{Ds18b20Error, Error},
-    scratchpad::Scratchpad,
-    Driver,
-};
-use embedded_hal::{
-    delay::DelayNs,
-    digital::{ErrorType, InputPin, OutputPin},
+    scratchpad::{Resolution, Scratchpad},
+    Ds18b20, OneWireBus, RetryPolicy, Temperatures, Transaction,
 };
 
 pub const COMMAND_MEMORY_CONVERT: u8 = 0x44;
@@ -15,10 +11,10 @@ pub const COMMAND_MEMORY_SCRATCHPAD_COPY: u8 = 0x48;
 pub const COMMAND_MEMORY_SCRATCHPAD_READ: u8 = 0xBE;
 pub const COMMAND_MEMORY_SCRATCHPAD_WRITE: u8 = 0x4E;
 
-const READ_SLOT_DURATION_MICROS: u16 = 70;
+pub(crate) const READ_SLOT_DURATION_MICROS: u16 = 70;
 
 /// Memory commands
-pub trait MemoryCommands<T: ErrorType> {
+pub trait MemoryCommands: OneWireBus {
     /// This command begins a temperature conversion. No further data is
     /// required. The temperature conversion will be performed and then the
     /// DS18B20 will remain idle. If the bus master issues read time slots
@@ -30,71 +26,170 @@ pub trait MemoryCommands<T: ErrorType> {
     ///
     /// You should wait for the measurement to finish before reading the
     /// measurement. The amount of time you need to wait depends on the current
-    /// resolution configuration
-    fn convert_temperature(&mut self) -> Result<(), Error<T::Error>>;
+    /// resolution configuration, see [`Ds18b20::start_conversion`] which
+    /// enables the strong pullup when needed.
+    fn convert_temperature(&mut self) -> Result<(), Error<Self::Error>>;
 
     /// Signals the mode of DS18B20 power supply to the master.
-    fn read_power_supply(&self) -> Result<(), Error<T::Error>>;
+    ///
+    /// Parasite-powered DS18B20s pull the bus low during the read time slot
+    /// following this command, externally powered ones let it remain high.
+    /// After a skip ROM command it tells whether any device on the bus is
+    /// parasite-powered. A parasite-powered device enables the strong pullup
+    /// of the bus, see [`OneWireBus::set_parasite_power`].
+    fn read_power_supply(&mut self) -> Result<PowerSupply, Error<Self::Error>>;
+
+    /// Reads the power supply mode of all devices simultaneously.
+    ///
+    /// Parasite if any device is parasite-powered. Unlike
+    /// [`read_power_supply`](Self::read_power_supply) it also disables the
+    /// strong pullup once all devices are externally powered, e.g. after the
+    /// parasite-powered ones have left the bus.
+    fn read_power_supply_all(&mut self) -> Result<PowerSupply, Error<Self::Error>>;
 
     /// Recalls values stored in nonvolatile memory (EEPROM, electrically
     /// erasable programmable read-only memory) into scratchpad (temperature
     /// triggers). Load config from EEPROM to scratchpad.
-    fn recall_eeprom(&mut self) -> Result<(), Error<T::Error>>;
+    fn recall_eeprom(&mut self) -> Result<(), Error<Self::Error>>;
 
     /// Copies scratchpad into nonvolatile memory (EEPROM) (addresses 2 through
     /// 4 only). Save config from scratchpad to EEPROM.
-    fn copy_scratchpad(&mut self) -> Result<(), Error<T::Error>>;
+    fn copy_scratchpad(&mut self) -> Result<(), Error<Self::Error>>;
 
     /// Reads bytes from scratchpad and reads CRC byte.
-    fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<T::Error>>;
+    fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<Self::Error>>;
 
     /// Writes bytes into scratchpad at addresses 2 through 4 (TH and TL
     /// temperature triggers and config).
-    fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<T::Error>>;
+    fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<Self::Error>>;
+
+    /// Begins a temperature conversion on all devices simultaneously.
+    ///
+    /// With parasite-powered devices on the bus the strong pullup is held for
+    /// the longest conversion time (12 bits), so the conversion is complete on
+    /// return.
+    fn start_conversion_all(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Converts the temperature on all devices simultaneously, waits for the
+    /// slowest of the given devices to finish, then reads the devices one by
+    /// one.
+    ///
+    /// A failed read of one device doesn't prevent reading the others, so the
+    /// result of each read is returned along with the device ROM.
+    fn convert_all<'a>(
+        &'a mut self,
+        devices: &'a [Ds18b20],
+    ) -> Result<Temperatures<'a, Self>, Error<Self::Error>>
+    where
+        Self: Sized;
 }
 
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> MemoryCommands<T> for Driver<T, U> {
-    fn convert_temperature(&mut self) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_MEMORY_CONVERT)?;
+impl<B: OneWireBus> MemoryCommands for B {
+    fn convert_temperature(&mut self) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_CONVERT)?;
         Ok(())
     }
 
-    fn read_power_supply(&self) -> Result<(), Error<T::Error>> {
-        Ok(())
+    fn read_power_supply(&mut self) -> Result<PowerSupply, Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_POWER_SUPPLY_READ)?;
+        if self.read_bit()? {
+            return Ok(PowerSupply::External);
+        }
+        self.set_parasite_power(true);
+        Ok(PowerSupply::Parasite)
     }
 
-    fn recall_eeprom(&mut self) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_MEMORY_RECALL)?;
+    fn read_power_supply_all(&mut self) -> Result<PowerSupply, Error<B::Error>> {
+        Transaction { rom: None }.begin(self)?;
+        let power_supply = self.read_power_supply()?;
+        self.set_parasite_power(power_supply == PowerSupply::Parasite);
+        Ok(power_supply)
+    }
+
+    fn recall_eeprom(&mut self) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_RECALL)?;
         // wait for the recall to finish (up to 10ms)
         let max_retries = (10000 / READ_SLOT_DURATION_MICROS) + 1;
         for _ in 0..max_retries {
-            if self.read_bit()? == true {
+            if self.read_bit()? {
                 return Ok(());
             }
         }
-        Err(Ds18b20Error::Timeout)?
+        Err(self.report(Ds18b20Error::Timeout.into()))
     }
 
-    fn copy_scratchpad(&mut self) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_MEMORY_SCRATCHPAD_COPY)?;
-        self.delay(10000); // delay 10ms for the write to complete
-        Ok(())
+    fn copy_scratchpad(&mut self) -> Result<(), Error<B::Error>> {
+        // wait for the write to complete
+        self.write_command_with_pull_up(
+            COMMAND_MEMORY_SCRATCHPAD_COPY,
+            self.copy_scratchpad_delay(),
+        )
     }
 
-    fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<T::Error>> {
-        self.write_byte(COMMAND_MEMORY_SCRATCHPAD_READ)?;
+    fn read_scratchpad(&mut self) -> Result<Scratchpad, Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_SCRATCHPAD_READ)?;
         let mut bytes = [0; 9];
         self.read_bytes(&mut bytes)?;
-        Ok(bytes.try_into()?)
+        bytes
+            .try_into()
+            .map_err(|error| self.report(Error::from(error)))
     }
 
-    fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_MEMORY_SCRATCHPAD_WRITE)?;
+    fn write_scratchpad(&mut self, scratchpad: Scratchpad) -> Result<(), Error<B::Error>> {
+        self.write_command(COMMAND_MEMORY_SCRATCHPAD_WRITE)?;
         self.write_byte(scratchpad.triggers.high as _)?;
         self.write_byte(scratchpad.triggers.low as _)?;
         self.write_byte(scratchpad.configuration_register.into())?;
         Ok(())
     }
+
+    fn start_conversion_all(&mut self) -> Result<(), Error<B::Error>> {
+        Transaction { rom: None }.begin(self)?;
+        convert(self, Resolution::Twelve.conversion_time())
+    }
+
+    fn convert_all<'a>(
+        &'a mut self,
+        devices: &'a [Ds18b20],
+    ) -> Result<Temperatures<'a, Self>, Error<B::Error>> {
+        convert_devices(self, devices)?;
+        Ok(Temperatures::new(self, devices, RetryPolicy::none()))
+    }
+}
+
+/// Converts the temperature on all devices simultaneously and waits for the
+/// slowest of the given devices to finish.
+pub(crate) fn convert_devices<B: OneWireBus>(
+    bus: &mut B,
+    devices: &[Ds18b20],
+) -> Result<(), Error<B::Error>> {
+    Transaction { rom: None }.begin(bus)?;
+    let conversion_time = devices
+        .iter()
+        .map(|device| device.resolution().conversion_time())
+        .max()
+        .unwrap_or_default();
+    bus.write_command_with_pull_up(COMMAND_MEMORY_CONVERT, conversion_time)
+}
+
+/// Begins a temperature conversion. With parasite-powered devices on the bus
+/// the strong pullup is held for the given time (ns).
+pub(crate) fn convert<B: OneWireBus>(bus: &mut B, ns: u32) -> Result<(), Error<B::Error>> {
+    if bus.parasite_power() {
+        bus.write_command_with_pull_up(COMMAND_MEMORY_CONVERT, ns)
+    } else {
+        bus.convert_temperature()
+    }
+}
+
+/// Power supply mode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PowerSupply {
+    /// Powered from the data line, needs a strong pullup during temperature
+    /// conversions and EEPROM writes.
+    Parasite,
+    /// Powered from an external supply through VDD.
+    External,
 }
 
 // impl<T: Command<Output = V>, U: Command<Output = V>, V> Command for And<T, U> {
@@ -4,27 +4,34 @@ use embedded_hal::{
     digital::{ErrorType, InputPin, OutputPin},
 };
 
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
-    /// Initialization.
+impl<T: InputPin + OutputPin + ErrorType, U: DelayNs, V, W> Driver<T, U, V, W> {
+    /// Reset pulse and presence detect of the initialization.
     ///
     /// All transactions on the 1-Wire bus begin with an initialization
     /// sequence. The initialization sequence consists of a reset pulse
     /// transmitted by the bus master followed by presence pulse(s) transmitted
     /// by the slave(s). The presence pulse lets the bus master know that the
     /// DS18B20 is on the bus and is ready to operate.
-    pub fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+    ///
+    /// Not observed, see [`OneWireBus::initialization`](crate::OneWireBus::initialization).
+    pub(crate) fn reset_pulse(&mut self) -> Result<bool, Error<T::Error>> {
         self.wait_for_high()?;
         self.set_low()?;
-        self.delay(self.configuration.h);
+        self.delay_ns(self.configuration().h);
+        self.presence_detect()
+    }
+
+    /// Releases the bus after the reset pulse and samples the presence pulse.
+    pub(crate) fn presence_detect(&mut self) -> Result<bool, Error<T::Error>> {
         self.set_high()?;
-        self.delay(self.configuration.i);
+        self.delay_ns(self.configuration().i);
         let presence = self.is_low()?;
-        self.delay(self.configuration.j);
+        self.delay_ns(self.configuration().j);
         Ok(presence)
     }
 
     /// wait up to 255 µs for the bus to become high (from the pull-up resistor)
-    fn wait_for_high(&mut self) -> Result<(), Error<T::Error>> {
+    pub(crate) fn wait_for_high(&mut self) -> Result<(), Error<T::Error>> {
         // wait up to 250 µs for the bus to become high (from the pull-up resistor)
         for _ in 0..125 {
             if self.is_high()? {
@@ -36,5 +43,5 @@ impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
     }
 }
 
-pub(crate) mod memory;
-pub(crate) mod rom;
+pub mod memory;
+pub mod rom;
@@ -1,14 +1,12 @@
-use crate::{error::Ds18b20Error, Driver, Error, Rom};
-use embedded_hal::{
-    delay::DelayNs,
-    digital::{ErrorType, InputPin, OutputPin},
-};
+use crate::{error::Ds18b20Error, Error, OneWireBus, Rom, Speed};
 
 pub const COMMAND_ALARM_SEARCH: u8 = 0xEC;
 pub const COMMAND_ROM_READ: u8 = 0x33;
 pub const COMMAND_ROM_MATCH: u8 = 0x55;
 pub const COMMAND_ROM_SKIP: u8 = 0xCC;
 pub const COMMAND_ROM_SEARCH: u8 = 0xF0;
+pub const COMMAND_ROM_OVERDRIVE_MATCH: u8 = 0x69;
+pub const COMMAND_ROM_OVERDRIVE_SKIP: u8 = 0x3C;
 
 const CONFLICT: (bool, bool) = (false, false);
 const ZERO: (bool, bool) = (false, true);
@@ -16,7 +14,7 @@ const ONE: (bool, bool) = (true, false);
 const NONE: (bool, bool) = (true, true);
 
 /// Rom commands
-pub trait RomCommands<T: ErrorType> {
+pub trait RomCommands: OneWireBus {
     /// Read ROM command
     ///
     /// This command allows the bus master to read the DS18B20’s 8-bit family
@@ -25,7 +23,7 @@ pub trait RomCommands<T: ErrorType> {
     /// is present on the bus, a data collision will occur when all slaves try
     /// to transmit at the same time (open drain will produce a wired AND
     /// result).
-    fn read_rom(&mut self) -> Result<Rom, Error<T::Error>>;
+    fn read_rom(&mut self) -> Result<Rom, Error<Self::Error>>;
 
     /// Match ROM command
     ///
@@ -35,7 +33,7 @@ pub trait RomCommands<T: ErrorType> {
     /// following memory function command. All slaves that do not match the
     /// 64-bit ROM sequence will wait for a reset pulse. This command can be
     /// used with a single or multiple devices on the bus.
-    fn match_rom(&mut self, rom: Rom) -> Result<(), Error<T::Error>>;
+    fn match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>>;
 
     /// Skip ROM command
     ///
@@ -45,7 +43,29 @@ pub trait RomCommands<T: ErrorType> {
     /// command is issued following the Skip ROM command, data collision will
     /// occur on the bus as multiple slaves transmit simultaneously (open drain
     /// pulldowns will produce a wired AND result).
-    fn skip_rom(&mut self) -> Result<(), Error<T::Error>>;
+    fn skip_rom(&mut self) -> Result<(), Error<Self::Error>>;
+
+    /// Overdrive match ROM command
+    ///
+    /// Sent at standard speed, the 64-bit ROM sequence follows at overdrive
+    /// speed. The matching overdrive-capable device switches to overdrive and
+    /// stays there until a reset at standard speed, the master is switched to
+    /// overdrive. See
+    /// [`standard_speed_initialization`](OneWireBus::standard_speed_initialization).
+    ///
+    /// A master without overdrive returns the error before the command is
+    /// sent, see [`OneWireBus::supports_speed`].
+    fn overdrive_match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>>;
+
+    /// Overdrive skip ROM command
+    ///
+    /// Sent at standard speed. All overdrive-capable devices switch to
+    /// overdrive and stay there until a reset at standard speed, the master is
+    /// switched to overdrive.
+    ///
+    /// A master without overdrive returns the error before the command is
+    /// sent, see [`OneWireBus::supports_speed`].
+    fn overdrive_skip_rom(&mut self) -> Result<(), Error<Self::Error>>;
 
     /// Search ROM command
     ///
@@ -53,248 +73,386 @@ pub trait RomCommands<T: ErrorType> {
     /// number of devices on the 1-Wire bus or their 64-bit ROM codes. The
     /// search ROM command allows the bus master to use a process of elimination
     /// to identify the 64-bit ROM codes of all slave devices on the bus.
-    fn search_rom(&mut self) -> Result<Rom, Error<T::Error>>;
+    ///
+    /// Each call finds the next device after the one recorded in `state` and
+    /// returns `None` once all devices have been found. Start with a default
+    /// state. Devices are always found in the same order (lowest to highest,
+    /// little endian). If the found ROM fails the CRC check, the error is
+    /// returned but the state still advances. On any other error the state is
+    /// left untouched, so the search can be retried.
+    fn search_rom(&mut self, state: &mut SearchState) -> Result<Option<Rom>, Error<Self::Error>>;
 
     /// Search alarm command
     ///
-    /// When a system is initially brought up, the bus master might not know the
-    /// number of devices on the 1-Wire bus or their 64-bit ROM codes. The
-    /// search ROM command allows the bus master to use a process of elimination
-    /// to identify the 64-bit ROM codes of all slave devices on the bus.
-    fn search_alarm(&self) -> Result<(), Error<T::Error>>;
+    /// The operation of this command is identical to the operation of the
+    /// search ROM command except that only slaves with a set alarm flag will
+    /// respond. This command allows the master device to determine if any
+    /// DS18B20s experienced an alarm condition during the most recent
+    /// temperature conversion. The alarm flag is set when the measured
+    /// temperature is higher than or equal to TH or lower than or equal to TL.
+    ///
+    /// The state is used the same way as in [`search_rom`](Self::search_rom).
+    fn search_alarm(&mut self, state: &mut SearchState) -> Result<Option<Rom>, Error<Self::Error>>;
+
+    /// Returns an iterator over the ROMs of all devices on the bus.
+    fn devices(&mut self) -> Devices<'_, Self>
+    where
+        Self: Sized;
+
+    /// Returns an iterator over the ROMs of the devices with a set alarm flag.
+    fn alarms(&mut self) -> Devices<'_, Self>
+    where
+        Self: Sized;
 }
 
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> RomCommands<T> for Driver<T, U> {
-    fn read_rom(&mut self) -> Result<Rom, Error<T::Error>> {
-        self.write_byte(COMMAND_ROM_READ)?;
+impl<B: OneWireBus> RomCommands for B {
+    fn read_rom(&mut self) -> Result<Rom, Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_READ)?;
         let mut bytes = [0; 8];
         self.read_bytes(&mut bytes)?;
-        Ok(bytes.try_into()?)
+        bytes
+            .try_into()
+            .map_err(|error| self.report(Error::from(error)))
     }
 
-    fn match_rom(&mut self, rom: Rom) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_ROM_MATCH)?;
+    fn match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_MATCH)?;
         let bytes: [u8; 8] = rom.into();
         self.write_bytes(&bytes)?;
         Ok(())
     }
 
-    fn skip_rom(&mut self) -> Result<(), Error<T::Error>> {
-        self.write_byte(COMMAND_ROM_SKIP)?;
+    fn skip_rom(&mut self) -> Result<(), Error<Self::Error>> {
+        self.write_command(COMMAND_ROM_SKIP)?;
         Ok(())
     }
 
-    fn search_rom(&mut self) -> Result<Rom, Error<T::Error>> {
-        // All transactions on the 1-Wire bus begin with an initialization
-        // sequence.
-        if !self.initialization()? {
-            Err(Ds18b20Error::NoAttachedDevices)?;
+    fn overdrive_match_rom(&mut self, rom: Rom) -> Result<(), Error<Self::Error>> {
+        check_overdrive(self)?;
+        self.write_command(COMMAND_ROM_OVERDRIVE_MATCH)?;
+        self.set_speed(Speed::Overdrive)?;
+        let bytes: [u8; 8] = rom.into();
+        self.write_bytes(&bytes)?;
+        Ok(())
+    }
+
+    fn overdrive_skip_rom(&mut self) -> Result<(), Error<Self::Error>> {
+        check_overdrive(self)?;
+        self.write_command(COMMAND_ROM_OVERDRIVE_SKIP)?;
+        self.set_speed(Speed::Overdrive)
+    }
+
+    fn search_rom(&mut self, state: &mut SearchState) -> Result<Option<Rom>, Error<Self::Error>> {
+        search(self, COMMAND_ROM_SEARCH, state)
+    }
+
+    fn search_alarm(&mut self, state: &mut SearchState) -> Result<Option<Rom>, Error<Self::Error>> {
+        search(self, COMMAND_ALARM_SEARCH, state)
+    }
+
+    fn devices(&mut self) -> Devices<'_, Self> {
+        Devices {
+            bus: self,
+            command: COMMAND_ROM_SEARCH,
+            state: Default::default(),
+            crc_errors: 0,
         }
-        self.write_byte(COMMAND_ROM_SEARCH)?;
-        let mut rom = 0;
-        let mut conflicts = 0;
-        for index in 0..u64::BITS {
-            let mask = 1u64 << index;
-            match (self.read_bit()?, self.read_bit()?) {
-                // `0b00`: There are still devices attached which have
-                // conflicting bits in this position.
-                CONFLICT => {
-                    // TODO:
-                    // discrepancies |= mask;
-                    // state.index = index;
-                    if conflicts & mask == 0 {
-                        rom &= !mask;
-                        self.write_bit(false)?;
-                    } else {
-                        rom |= mask;
-                        self.write_bit(true)?;
-                    }
-                }
-                // `0b01`: All devices still coupled have a 0-bit in this bit
-                // position.
-                ZERO => {
-                    rom |= mask;
-                    self.write_bit(false)?;
-                }
-                // `0b10`: All devices still coupled have a 1-bit in this bit
-                // position.
-                ONE => {
-                    rom &= !mask;
-                    self.write_bit(true)?;
+    }
+
+    fn alarms(&mut self) -> Devices<'_, Self> {
+        Devices {
+            bus: self,
+            command: COMMAND_ALARM_SEARCH,
+            state: Default::default(),
+            crc_errors: 0,
+        }
+    }
+}
+
+/// Fails if the master can't switch to overdrive, the devices would be left
+/// at overdrive speed by the overdrive ROM commands.
+fn check_overdrive<B: OneWireBus>(bus: &mut B) -> Result<(), Error<B::Error>> {
+    if !bus.supports_speed(Speed::Overdrive) {
+        let speed = Speed::Overdrive;
+        return Err(bus.report(Ds18b20Error::UnsupportedSpeed { speed }.into()));
+    }
+    Ok(())
+}
+
+/// Search for the next device after the one recorded in the state.
+///
+/// The search ROM and the alarm search commands share this algorithm. At
+/// each bit position all devices still coupled send the bit and its
+/// complement, the master chooses a direction and the devices which don't
+/// match it go idle until the next reset.
+pub(crate) fn search<B: OneWireBus>(
+    bus: &mut B,
+    command: u8,
+    state: &mut SearchState,
+) -> Result<Option<Rom>, Error<B::Error>> {
+    if state.finished {
+        return Ok(None);
+    }
+    // All transactions on the 1-Wire bus begin with an initialization
+    // sequence.
+    if !bus.initialization()? {
+        return Err(bus.report(Ds18b20Error::NoAttachedDevices.into()));
+    }
+    bus.write_command(command)?;
+    let mut search = Search::new(state);
+    let directions = (0..u64::BITS).fold(0, |directions, index| {
+        directions | (search.direction(index) as u64) << index
+    });
+    let (bits, complements) = bus.triplets(directions)?;
+    for index in 0..u64::BITS {
+        let response = (
+            bits >> index & 0x01 == 0x01,
+            complements >> index & 0x01 == 0x01,
+        );
+        match search.step(index, response) {
+            Ok(Some(_)) => {}
+            Ok(None) => return Ok(None),
+            Err(error) => return Err(bus.report(error.into())),
+        }
+    }
+    search
+        .finish()
+        .map(Some)
+        .map_err(|error| bus.report(error.into()))
+}
+
+/// Search state
+///
+/// Records the last found ROM and the last position where the search had to
+/// choose between devices, so the search can be continued from there.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchState {
+    rom: u64,
+    last_discrepancy: Option<u32>,
+    finished: bool,
+}
+
+impl SearchState {
+    /// Returns `true` if all devices have been found.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// A single pass of the search algorithm.
+///
+/// Chooses the direction at each bit position from the responses of the
+/// devices, independently of how the bits are transferred.
+pub(crate) struct Search<'a> {
+    state: &'a mut SearchState,
+    rom: u64,
+    last_discrepancy: Option<u32>,
+}
+
+impl<'a> Search<'a> {
+    pub(crate) fn new(state: &'a mut SearchState) -> Self {
+        Self {
+            state,
+            rom: 0,
+            last_discrepancy: None,
+        }
+    }
+
+    /// Returns the direction to take at the given position if the devices
+    /// have conflicting bits there.
+    pub(crate) fn direction(&self, index: u32) -> bool {
+        self.expected(index).unwrap_or(false)
+    }
+
+    /// Returns the direction the previous search took at the given position,
+    /// if it has to be followed again.
+    fn expected(&self, index: u32) -> Option<bool> {
+        match self.state.last_discrepancy {
+            Some(last_discrepancy) if index < last_discrepancy => {
+                Some(self.state.rom & (1 << index) != 0)
+            }
+            Some(last_discrepancy) if index == last_discrepancy => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Returns the bit to write back to the devices, or `None` if no device
+    /// takes part in the search.
+    pub(crate) fn step(
+        &mut self,
+        index: u32,
+        response: (bool, bool),
+    ) -> Result<Option<bool>, Ds18b20Error> {
+        let expected = self.expected(index);
+        let bit = match response {
+            // `0b00`: There are still devices attached which have conflicting
+            // bits in this position.
+            CONFLICT => {
+                let bit = expected.unwrap_or(false);
+                if !bit {
+                    self.last_discrepancy = Some(index);
                 }
-                // `0b11`: There are no devices attached to the 1-Wire bus.
-                NONE => Err(Ds18b20Error::NoAttachedDevices)?,
+                bit
             }
+            // `0b01`: All devices still coupled have a 0-bit in this bit
+            // position.
+            ZERO => false,
+            // `0b10`: All devices still coupled have a 1-bit in this bit
+            // position.
+            ONE => true,
+            // `0b11`: There are no devices taking part in the search, e.g.
+            // none of them has its alarm flag set.
+            NONE if index == 0 && expected.is_none() => {
+                self.state.finished = true;
+                return Ok(None);
+            }
+            // `0b11`: The devices taking part in the search have left the bus.
+            NONE => Err(Ds18b20Error::UnexpectedResponse)?,
+        };
+        // The devices on the branch being followed have left the bus.
+        if expected.is_some_and(|expected| expected != bit) {
+            Err(Ds18b20Error::UnexpectedResponse)?;
+        }
+        if bit {
+            self.rom |= 1 << index;
         }
-        Ok(rom.try_into()?)
+        Ok(Some(bit))
+    }
+
+    /// Updates the state and returns the found ROM.
+    pub(crate) fn finish(self) -> Result<Rom, Ds18b20Error> {
+        // The search moves on even if the CRC check fails.
+        *self.state = SearchState {
+            rom: self.rom,
+            last_discrepancy: self.last_discrepancy,
+            finished: self.last_discrepancy.is_none(),
+        };
+        self.rom.try_into()
     }
+}
+
+/// The number of consecutive CRC errors after which the search is given up.
+const MAX_CRC_ERRORS: u8 = 3;
 
-    fn search_alarm(&self) -> Result<(), Error<T::Error>> {
-        unimplemented!()
+/// Devices
+///
+/// An iterator over the ROMs of the devices on the bus (or of the alarming
+/// devices only). A CRC error is yielded and the search moves on, unless it
+/// is the third in a row (the bus is too noisy to go on), any other error
+/// ends the iteration.
+pub struct Devices<'a, B> {
+    bus: &'a mut B,
+    command: u8,
+    state: SearchState,
+    /// Consecutive CRC errors.
+    crc_errors: u8,
+}
+
+impl<B: OneWireBus> Iterator for Devices<'_, B> {
+    type Item = Result<Rom, Error<B::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match search(self.bus, self.command, &mut self.state) {
+            Ok(rom) => {
+                self.crc_errors = 0;
+                rom.map(Ok)
+            }
+            Err(error) => {
+                // A CRC error doesn't stop the search, unless it keeps
+                // failing, any other error does.
+                let crc = matches!(error, Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. }));
+                self.crc_errors += crc as u8;
+                if !crc || self.crc_errors == MAX_CRC_ERRORS {
+                    self.state.finished = true;
+                }
+                Some(Err(error))
+            }
+        }
     }
 }
 
-// pub struct Iter<'a, T, U> {
-//     driver: &'a mut Driver<T, U>,
-//     discrepancies: u64,
-//     index: u8,
-// }
-
-// impl<T, U> Iterator for Iter<'_, T, U> {
-//     type Item = Result<Rom>;
-
-//     fn next(&mut self) -> Option<Self::Item> {
-//         None
-//     }
-// }
-
-// /// Search for device addresses on the bus
-// ///
-// /// They can be filtered to only alarming devices if needed Start the first
-// /// search with a search_state of `None`, then use the returned state for
-// /// subsequent searches There is no time limit for continuing a search, but
-// /// if devices are added / removed / change alarm state, the search may
-// /// return an error or fail to find a device Device addresses will always be
-// /// returned in the same order (lowest to highest, Little Endian)
-// pub fn device_search(
-//     &mut self,
-//     search_state: Option<&SearchState>,
-//     only_alarming: bool,
-// ) -> Result<Option<(Address, SearchState)>> {
-//     if let Some(search_state) = search_state {
-//         if search_state.discrepancies == 0 {
-//             return Ok(None);
-//         }
-//     }
-
-//     if !self.reset()? {
-//         return Ok(None);
-//     }
-//     if only_alarming {
-//         self.write_byte(COMMAND_ALARM_SEARCH)?;
-//     } else {
-//         self.write_byte(COMMAND_ROM_SEARCH)?;
-//     }
-
-//     let mut last_discrepancy_index: u8 = 0;
-//     let mut address;
-//     let mut discrepancies;
-//     let continue_start_bit;
-
-//     if let Some(search_state) = search_state {
-//         // follow up to the last discrepancy
-//         for bit_index in 0..search_state.last_discrepancy_index {
-//             let _false_bit = !self.read_bit()?;
-//             let _true_bit = !self.read_bit()?;
-//             let was_discrepancy_bit =
-//                 (search_state.discrepancies & (1_u64 << (bit_index as u64))) != 0;
-//             if was_discrepancy_bit {
-//                 last_discrepancy_index = bit_index;
-//             }
-//             let previous_chosen_bit = (search_state.address & (1_u64 << (bit_index as u64))) != 0;
-
-//             // choose the same as last time
-//             self.write_bit(previous_chosen_bit)?;
-//         }
-//         address = search_state.address;
-//         // This is the discrepancy bit. False is always chosen to start, so choose true this time
-//         {
-//             let false_bit = !self.read_bit()?;
-//             let true_bit = !self.read_bit()?;
-//             if !(false_bit && true_bit) {
-//                 // A different response was received than last search
-//                 return Err(Error::UnexpectedResponse);
-//             }
-//             let address_mask = 1_u64 << (search_state.last_discrepancy_index as u64);
-//             address |= address_mask;
-//             self.write_bit(true)?;
-//         }
-
-//         //keep all discrepancies except the last one
-//         discrepancies =
-//             search_state.discrepancies & !(1_u64 << (search_state.last_discrepancy_index as u64));
-//         continue_start_bit = search_state.last_discrepancy_index + 1;
-//     } else {
-//         address = 0;
-//         discrepancies = 0;
-//         continue_start_bit = 0;
-//     }
-//     for bit_index in continue_start_bit..64 {
-//         let false_bit = !self.read_bit()?;
-//         let true_bit = !self.read_bit()?;
-//         let chosen_bit = match (false_bit, true_bit) {
-//             (false, false) => {
-//                 // No devices responded to the search request
-//                 return Err(Error::UnexpectedResponse);
-//             }
-//             (false, true) => {
-//                 // All remaining devices have the true bit set
-//                 true
-//             }
-//             (true, false) => {
-//                 // All remaining devices have the false bit set
-//                 false
-//             }
-//             (true, true) => {
-//                 // Discrepancy, multiple values reported
-//                 // choosing the lower value here
-//                 discrepancies |= 1_u64 << (bit_index as u64);
-//                 last_discrepancy_index = bit_index;
-//                 false
-//             }
-//         };
-//         let address_mask = 1_u64 << (bit_index as u64);
-//         if chosen_bit {
-//             address |= address_mask;
-//         } else {
-//             address &= !address_mask;
-//         }
-//         self.write_bit(chosen_bit)?;
-//     }
-//     check(&address.to_le_bytes())?;
-//     Ok(Some((
-//         Address(address),
-//         SearchState {
-//             address,
-//             discrepancies,
-//             last_discrepancy_index,
-//         },
-//     )))
-// }
-
-// /// Devices
-// pub struct Devices<'a, T, U> {
-//     one_wire: &'a mut OneWire<T, U>,
-//     state: Option<SearchState>,
-//     finished: bool,
-//     only_alarming: bool,
-// }
-
-// impl<'a, T: Pin, D: DelayUs> Iterator for Devices<'a, T, D> {
-//     type Item = Result<Address>;
-
-//     fn next(&mut self) -> Option<Self::Item> {
-//         if self.finished {
-//             return None;
-//         }
-//         let result = self
-//             .one_wire
-//             .device_search(self.state.as_ref(), self.only_alarming);
-//         match result {
-//             Ok(Some((address, search_state))) => {
-//                 self.state = Some(search_state);
-//                 Some(Ok(address))
-//             }
-//             Ok(None) => {
-//                 self.state = None;
-//                 self.finished = true;
-//                 None
-//             }
-//             Err(error) => {
-//                 self.state = None;
-//                 self.finished = true;
-//                 Some(Err(error))
-//             }
-//         }
-//     }
-// }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Configuration, Driver};
+    use embedded_hal_mock::eh1::{
+        delay::NoopDelay,
+        digital::{Mock, State, Transaction},
+    };
+
+    extern crate std;
+
+    #[test]
+    fn overdrive() {
+        let mut transactions = std::vec![Transaction::set(State::High)];
+        // Overdrive skip ROM: 8 write slots.
+        transactions.extend(
+            (0..8).flat_map(|_| [Transaction::set(State::Low), Transaction::set(State::High)]),
+        );
+        // Standard speed initialization, with a presence pulse.
+        transactions.extend([
+            Transaction::get(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::get(State::Low),
+        ]);
+        let mut pin = Mock::new(&transactions);
+        let mut driver = Driver::new(pin.clone(), NoopDelay).unwrap();
+        // Custom timing is kept across the speed switches.
+        let long_line = Configuration::long_line();
+        *driver.configuration_mut() = long_line;
+        driver.overdrive_skip_rom().unwrap();
+        assert_eq!(Speed::Overdrive, driver.speed());
+        assert_eq!(&Configuration::overdrive(), driver.configuration());
+        assert_eq!(Ok(true), driver.standard_speed_initialization());
+        assert_eq!(Speed::Standard, driver.speed());
+        assert_eq!(&long_line, driver.configuration());
+        assert_eq!(
+            &Configuration::overdrive(),
+            driver.configuration_for(Speed::Overdrive)
+        );
+        pin.done();
+    }
+
+    /// Every bit but the first conflicts, so every pass finds a new ROM and
+    /// (nearly) all of them fail the CRC check.
+    struct Noisy;
+
+    impl OneWireBus for Noisy {
+        type Error = core::convert::Infallible;
+
+        fn initialization(&mut self) -> Result<bool, Error<Self::Error>> {
+            Ok(true)
+        }
+
+        fn read_bit(&mut self) -> Result<bool, Error<Self::Error>> {
+            Ok(false)
+        }
+
+        fn write_bit(&mut self, _bit: bool) -> Result<(), Error<Self::Error>> {
+            Ok(())
+        }
+
+        fn delay(&mut self, _ns: u32) {}
+
+        fn parasite_power(&self) -> bool {
+            false
+        }
+
+        fn set_parasite_power(&mut self, _parasite_power: bool) {}
+
+        fn triplets(&mut self, _directions: u64) -> Result<(u64, u64), Error<Self::Error>> {
+            Ok((0x01, 0x00))
+        }
+    }
+
+    #[test]
+    fn crc_errors() {
+        let errors: std::vec::Vec<_> = Noisy.devices().collect();
+        assert_eq!(MAX_CRC_ERRORS as usize, errors.len());
+        assert!(errors.iter().all(|error| matches!(
+            error,
+            Err(Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. }))
+        )));
+    }
+}
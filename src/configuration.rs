@@ -1,19 +1,27 @@
+use crate::error::ConfigurationError;
+
+/// EEPROM write time of the copy scratchpad command, up to 10 ms.
+pub(crate) const COPY_SCRATCHPAD_DELAY: u32 = 10_000_000;
+
 /// Configuration
+///
+/// The timings (ns) of the 1-Wire slots, named after the Maxim application
+/// note 126. Use [`Configuration::builder`] to validate them.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Configuration {
-    /// A;.
+    /// A; write 1 low time and read low time, standard: 1-15 μs.
     pub a: u32,
-    /// B;.
+    /// B; write 1 high time, standard: slot (A + B) min 60 μs.
     pub b: u32,
-    /// C;.
+    /// C; write 0 low time, standard: 60-120 μs.
     pub c: u32,
-    /// D;.
+    /// D; write 0 recovery time, standard: min 1 μs.
     pub d: u32,
-    /// E;.
+    /// E; read sample delay, standard: sample point (A + E) max 15 μs.
     pub e: u32,
-    /// F;.
+    /// F; read recovery time, standard: slot (A + E + F) min 60 μs.
     pub f: u32,
-    /// G;.
+    /// G; delay before the reset.
     pub g: u32,
     /// H; min: 480 μs, max: 960 μs.
     pub h: u32,
@@ -26,6 +34,35 @@ pub struct Configuration {
 }
 
 impl Configuration {
+    /// Returns a builder starting from the preset.
+    pub fn builder(preset: Preset) -> ConfigurationBuilder {
+        ConfigurationBuilder {
+            speed: preset.speed(),
+            configuration: preset.into(),
+            sample_point: None,
+            recovery: None,
+        }
+    }
+
+    /// Standard speed for long lines (tens of meters), after the Maxim
+    /// long-line guidance: a later sample point and longer recovery times let
+    /// the line charge back through the pull-up resistor.
+    pub const fn long_line() -> Self {
+        Self {
+            a: 8_000,
+            b: 67_000,
+            c: 60_000,
+            d: 15_000,
+            e: 6_000,
+            f: 61_000,
+            g: 0_000,
+            h: 480_000,
+            i: 70_000,
+            j: 410_000,
+            copy_scratchpad_delay: COPY_SCRATCHPAD_DELAY,
+        }
+    }
+
     pub const fn overdrive() -> Self {
         Self {
             a: 1_000,
@@ -38,7 +75,7 @@ impl Configuration {
             h: 70_000,
             i: 8_500,
             j: 40_000,
-            copy_scratchpad_delay: 10_000_000,
+            copy_scratchpad_delay: COPY_SCRATCHPAD_DELAY,
         }
     }
 
@@ -54,7 +91,7 @@ impl Configuration {
             h: 480_000,
             i: 70_000,
             j: 410_000,
-            copy_scratchpad_delay: 10_000_000,
+            copy_scratchpad_delay: COPY_SCRATCHPAD_DELAY,
         }
     }
 }
@@ -65,36 +102,297 @@ impl Default for Configuration {
     }
 }
 
-// /// Speed
-// #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-// pub enum Speed {
-//     #[default]
-//     Standard,
-//     Overdrive,
-// }
-
-// mod standard {
-//     pub(super) const A: u32 = 6;
-//     pub(super) const B: u32 = 64;
-//     pub(super) const C: u32 = 60;
-//     pub(super) const D: u32 = 10;
-//     pub(super) const E: u32 = 9;
-//     pub(super) const F: u32 = 55;
-//     pub(super) const G: u32 = 0;
-//     pub(super) const H: u32 = 480;
-//     pub(super) const I: u32 = 70;
-//     pub(super) const J: u32 = 410;
-// }
-
-// mod overdrive {
-//     pub(super) const A: f32 = 1.0;
-//     pub(super) const B: f32 = 7.5;
-//     pub(super) const C: f32 = 7.5;
-//     pub(super) const D: f32 = 2.5;
-//     pub(super) const E: f32 = 1.0;
-//     pub(super) const F: f32 = 7.0;
-//     pub(super) const G: f32 = 2.5;
-//     pub(super) const H: f32 = 70.0;
-//     pub(super) const I: f32 = 8.5;
-//     pub(super) const J: f32 = 40.0;
-// }
+/// Speed
+///
+/// Overdrive-capable devices enter overdrive with an overdrive ROM command and
+/// leave it with a reset at standard speed. The DS18B20 has standard speed
+/// only.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Speed {
+    #[default]
+    Standard,
+    Overdrive,
+}
+
+impl From<Speed> for Configuration {
+    fn from(speed: Speed) -> Self {
+        match speed {
+            Speed::Standard => Self::standard(),
+            Speed::Overdrive => Self::overdrive(),
+        }
+    }
+}
+
+impl Configuration {
+    /// Checks the timings against the 1-Wire specification for the speed.
+    pub fn validate(&self, speed: Speed) -> Result<(), ConfigurationError> {
+        let limits = match speed {
+            Speed::Standard => STANDARD,
+            Speed::Overdrive => OVERDRIVE,
+        };
+        let check = |field, value, min, max| {
+            if value < min {
+                Err(ConfigurationError::TooShort {
+                    field,
+                    value,
+                    min,
+                    speed,
+                })
+            } else if value > max {
+                Err(ConfigurationError::TooLong {
+                    field,
+                    value,
+                    max,
+                    speed,
+                })
+            } else {
+                Ok(())
+            }
+        };
+        check("A", self.a, limits.a.0, limits.a.1)?;
+        check(
+            "A + B",
+            self.a.saturating_add(self.b),
+            limits.slot,
+            u32::MAX,
+        )?;
+        check("C", self.c, limits.c.0, limits.c.1)?;
+        check("D", self.d, limits.recovery, u32::MAX)?;
+        check("A + E", self.a.saturating_add(self.e), 0, limits.sample)?;
+        let read_slot = self.a.saturating_add(self.e).saturating_add(self.f);
+        check("A + E + F", read_slot, limits.slot, u32::MAX)?;
+        check("H", self.h, limits.h.0, limits.h.1)?;
+        check("I", self.i, limits.i.0, limits.i.1)?;
+        check("I + J", self.i.saturating_add(self.j), limits.h.0, u32::MAX)?;
+        check(
+            "copy scratchpad delay",
+            self.copy_scratchpad_delay,
+            COPY_SCRATCHPAD_DELAY,
+            u32::MAX,
+        )?;
+        Ok(())
+    }
+}
+
+/// Limits (ns)
+struct Limits {
+    a: (u32, u32),
+    c: (u32, u32),
+    slot: u32,
+    recovery: u32,
+    sample: u32,
+    h: (u32, u32),
+    i: (u32, u32),
+}
+
+const STANDARD: Limits = Limits {
+    a: (1_000, 15_000),
+    c: (60_000, 120_000),
+    slot: 60_000,
+    recovery: 1_000,
+    sample: 15_000,
+    h: (480_000, 960_000),
+    i: (15_000, 300_000),
+};
+
+const OVERDRIVE: Limits = Limits {
+    a: (1_000, 2_000),
+    c: (6_000, 16_000),
+    slot: 6_000,
+    recovery: 1_000,
+    sample: 2_000,
+    h: (48_000, 80_000),
+    i: (2_000, 10_000),
+};
+
+/// Preset
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Preset {
+    #[default]
+    Standard,
+    Overdrive,
+    /// Standard speed for long lines, see [`Configuration::long_line`].
+    LongLine,
+}
+
+impl Preset {
+    pub fn speed(&self) -> Speed {
+        match self {
+            Preset::Standard | Preset::LongLine => Speed::Standard,
+            Preset::Overdrive => Speed::Overdrive,
+        }
+    }
+}
+
+impl From<Preset> for Configuration {
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::Standard => Self::standard(),
+            Preset::Overdrive => Self::overdrive(),
+            Preset::LongLine => Self::long_line(),
+        }
+    }
+}
+
+/// Configuration builder
+///
+/// Starts from a preset, the timings are validated for the speed of the
+/// preset on [`build`](Self::build).
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigurationBuilder {
+    speed: Speed,
+    configuration: Configuration,
+    sample_point: Option<u32>,
+    recovery: Option<u32>,
+}
+
+impl ConfigurationBuilder {
+    pub fn a(mut self, a: u32) -> Self {
+        self.configuration.a = a;
+        self
+    }
+
+    pub fn b(mut self, b: u32) -> Self {
+        self.configuration.b = b;
+        self
+    }
+
+    pub fn c(mut self, c: u32) -> Self {
+        self.configuration.c = c;
+        self
+    }
+
+    pub fn d(mut self, d: u32) -> Self {
+        self.configuration.d = d;
+        self
+    }
+
+    pub fn e(mut self, e: u32) -> Self {
+        self.configuration.e = e;
+        self
+    }
+
+    pub fn f(mut self, f: u32) -> Self {
+        self.configuration.f = f;
+        self
+    }
+
+    pub fn g(mut self, g: u32) -> Self {
+        self.configuration.g = g;
+        self
+    }
+
+    pub fn h(mut self, h: u32) -> Self {
+        self.configuration.h = h;
+        self
+    }
+
+    pub fn i(mut self, i: u32) -> Self {
+        self.configuration.i = i;
+        self
+    }
+
+    pub fn j(mut self, j: u32) -> Self {
+        self.configuration.j = j;
+        self
+    }
+
+    pub fn copy_scratchpad_delay(mut self, copy_scratchpad_delay: u32) -> Self {
+        self.configuration.copy_scratchpad_delay = copy_scratchpad_delay;
+        self
+    }
+
+    /// Sets the read sample point (ns), from the start of the slot.
+    ///
+    /// Replaces E with the sample point less A, keeping the read slot length.
+    pub fn sample_point(mut self, sample_point: u32) -> Self {
+        self.sample_point = Some(sample_point);
+        self
+    }
+
+    /// Sets the recovery time (ns) after a write 0 slot.
+    ///
+    /// Replaces D, then B and F so that all slots last C + D.
+    pub fn recovery(mut self, recovery: u32) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    /// Returns the validated configuration.
+    pub fn build(self) -> Result<Configuration, ConfigurationError> {
+        let mut configuration = self.configuration;
+        if let Some(sample_point) = self.sample_point {
+            let read_slot = configuration
+                .a
+                .saturating_add(configuration.e)
+                .saturating_add(configuration.f);
+            configuration.e = sample_point.saturating_sub(configuration.a);
+            configuration.f = read_slot.saturating_sub(sample_point);
+        }
+        if let Some(recovery) = self.recovery {
+            let slot = configuration.c.saturating_add(recovery);
+            configuration.d = recovery;
+            configuration.b = slot.saturating_sub(configuration.a);
+            configuration.f = slot.saturating_sub(configuration.a.saturating_add(configuration.e));
+        }
+        configuration.validate(self.speed)?;
+        Ok(configuration)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        for preset in [Preset::Standard, Preset::Overdrive, Preset::LongLine] {
+            let configuration = Configuration::from(preset);
+            assert_eq!(Ok(configuration), Configuration::builder(preset).build());
+        }
+    }
+
+    #[test]
+    fn builder() {
+        assert_eq!(
+            Err(ConfigurationError::TooShort {
+                field: "H",
+                value: 400_000,
+                min: 480_000,
+                speed: Speed::Standard,
+            }),
+            Configuration::builder(Preset::Standard).h(400_000).build()
+        );
+        assert_eq!(
+            Err(ConfigurationError::TooShort {
+                field: "copy scratchpad delay",
+                value: 5_000_000,
+                min: 10_000_000,
+                speed: Speed::Standard,
+            }),
+            Configuration::builder(Preset::Standard)
+                .copy_scratchpad_delay(5_000_000)
+                .build()
+        );
+        assert_eq!(
+            Err(ConfigurationError::TooLong {
+                field: "A + E",
+                value: 20_000,
+                max: 15_000,
+                speed: Speed::Standard,
+            }),
+            Configuration::builder(Preset::Standard)
+                .sample_point(20_000)
+                .build()
+        );
+        let configuration = Configuration::builder(Preset::LongLine)
+            .sample_point(13_000)
+            .recovery(20_000)
+            .build()
+            .unwrap();
+        assert_eq!((5_000, 20_000), (configuration.e, configuration.d));
+        // 80 μs slots
+        assert_eq!(80_000, configuration.a + configuration.b);
+        assert_eq!(80_000, configuration.a + configuration.e + configuration.f);
+    }
+}
@@ -0,0 +1,414 @@
+//! 1-Wire master on a DS2480B serial line driver (DS9097U adapters).
+//!
+//! The DS2480B is driven over a serial port at 9600 baud. It has a command
+//! mode, where every byte is a command (reset, single bit, search accelerator,
+//! pulse, configuration), and a data mode, where every byte is sent to the
+//! 1-Wire bus and the byte read back is returned.
+
+use crate::{Ds18b20Error, Error, OneWireBus};
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+const MODE_DATA: u8 = 0xE1;
+const MODE_COMMAND: u8 = 0xE3;
+
+const COMMAND_RESET: u8 = 0xC1;
+const COMMAND_SINGLE_BIT: u8 = 0x81;
+/// Arms the strong pull-up after the single bit.
+const SINGLE_BIT_PRIME: u8 = 0x02;
+const COMMAND_SEARCH_ACCELERATOR_ON: u8 = 0xB1;
+const COMMAND_SEARCH_ACCELERATOR_OFF: u8 = 0xA1;
+const COMMAND_PULSE_TERMINATE: u8 = 0xF1;
+/// Strong pull-up duration: infinite, until terminated.
+const COMMAND_CONFIGURATION_SPUD_INFINITE: u8 = 0x3F;
+
+/// Presence result of the reset command.
+const RESET_SHORTED: u8 = 0b00;
+const RESET_NO_PRESENCE: u8 = 0b11;
+
+/// Speed
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Speed {
+    #[default]
+    Regular,
+    /// Regular speed with slew rate control, for long lines.
+    Flexible,
+    Overdrive,
+}
+
+impl Speed {
+    fn bits(self) -> u8 {
+        match self {
+            Speed::Regular => 0b00 << 2,
+            Speed::Flexible => 0b01 << 2,
+            Speed::Overdrive => 0b10 << 2,
+        }
+    }
+}
+
+/// Mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    Command,
+    Data,
+}
+
+/// DS2480B 1-Wire master
+#[derive(Debug)]
+pub struct Ds2480b<T> {
+    port: T,
+    mode: Mode,
+    speed: Speed,
+    parasite_power: bool,
+}
+
+impl<T> Ds2480b<T> {
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    pub fn bus_speed(&self) -> Speed {
+        self.speed
+    }
+
+    /// Sets the speed of the following commands, including the flexible
+    /// speed not covered by [`OneWireBus::set_speed`].
+    ///
+    /// Devices enter overdrive only with an overdrive ROM command and leave it
+    /// with a reset at regular speed.
+    pub fn set_bus_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+    }
+}
+
+impl<T: Read + Write> Ds2480b<T> {
+    /// Calibrates the DS2480B timing and configures the strong pull-up.
+    ///
+    /// The port must be at 9600 baud, 8N1. The DS2480B should be reset
+    /// beforehand by a serial break (or by power cycling the adapter).
+    pub fn new(port: T) -> Result<Self, Error<io::Error>> {
+        let mut ds2480b = Self {
+            port,
+            mode: Mode::Command,
+            speed: Default::default(),
+            parasite_power: false,
+        };
+        // The timing byte, a reset command without response.
+        ds2480b.write(&[COMMAND_RESET])?;
+        thread::sleep(Duration::from_millis(5));
+        ds2480b.command(COMMAND_CONFIGURATION_SPUD_INFINITE)?;
+        Ok(ds2480b)
+    }
+
+    /// Sends a command and returns the response.
+    fn command(&mut self, command: u8) -> Result<u8, Error<io::Error>> {
+        self.set_mode(Mode::Command)?;
+        self.write(&[command])?;
+        let mut response = [0];
+        self.read(&mut response)?;
+        Ok(response[0])
+    }
+
+    /// Sends data bytes to the 1-Wire bus and reads the response back into
+    /// the same buffer.
+    fn data(&mut self, bytes: &mut [u8]) -> Result<(), Error<io::Error>> {
+        self.set_mode(Mode::Data)?;
+        for byte in bytes.iter_mut() {
+            // The mode switch byte is sent twice in data mode.
+            if *byte == MODE_COMMAND {
+                self.write(&[MODE_COMMAND, MODE_COMMAND])?;
+            } else {
+                self.write(&[*byte])?;
+            }
+        }
+        self.read(bytes)
+    }
+
+    fn set_mode(&mut self, mode: Mode) -> Result<(), Error<io::Error>> {
+        if self.mode != mode {
+            self.write(&[match mode {
+                Mode::Command => MODE_COMMAND,
+                Mode::Data => MODE_DATA,
+            }])?;
+            self.mode = mode;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error<io::Error>> {
+        self.port.write_all(bytes).map_err(Error::Bus)?;
+        self.port.flush().map_err(Error::Bus)
+    }
+
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), Error<io::Error>> {
+        self.port
+            .read_exact(bytes)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::UnexpectedEof | io::ErrorKind::TimedOut => {
+                    Error::Ds18b20(Ds18b20Error::Timeout)
+                }
+                _ => Error::Bus(error),
+            })
+    }
+}
+
+impl<T: Read + Write> OneWireBus for Ds2480b<T> {
+    type Error = io::Error;
+
+    fn initialization(&mut self) -> Result<bool, Error<io::Error>> {
+        let response = self.command(COMMAND_RESET | self.speed.bits())?;
+        match response & 0b11 {
+            RESET_SHORTED => Err(Ds18b20Error::BusNotHigh)?,
+            RESET_NO_PRESENCE => Ok(false),
+            // Presence or alarming presence.
+            _ => Ok(true),
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error<io::Error>> {
+        let response = self.command(COMMAND_SINGLE_BIT | 0x10 | self.speed.bits())?;
+        Ok(response & 0b11 == 0b11)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<io::Error>> {
+        self.command(COMMAND_SINGLE_BIT | (bit as u8) << 4 | self.speed.bits())?;
+        Ok(())
+    }
+
+    fn delay(&mut self, ns: u32) {
+        thread::sleep(Duration::from_nanos(ns as _));
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error<io::Error>> {
+        let mut bytes = [0xFF];
+        self.data(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), Error<io::Error>> {
+        bytes.fill(0xFF);
+        self.data(bytes)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error<io::Error>> {
+        self.data(&mut [byte])
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<io::Error>> {
+        for byte in bytes {
+            self.write_byte(*byte)?;
+        }
+        Ok(())
+    }
+
+    fn speed(&self) -> crate::Speed {
+        match self.speed {
+            Speed::Regular | Speed::Flexible => crate::Speed::Standard,
+            Speed::Overdrive => crate::Speed::Overdrive,
+        }
+    }
+
+    fn supports_speed(&self, _speed: crate::Speed) -> bool {
+        true
+    }
+
+    /// Keeps the flexible speed at standard speed.
+    fn set_speed(&mut self, speed: crate::Speed) -> Result<(), Error<io::Error>> {
+        self.speed = match (speed, self.speed) {
+            (crate::Speed::Standard, Speed::Overdrive) => Speed::Regular,
+            (crate::Speed::Standard, speed) => speed,
+            (crate::Speed::Overdrive, _) => Speed::Overdrive,
+        };
+        Ok(())
+    }
+
+    /// Writes the command as single bits if parasite-powered devices are on
+    /// the bus, the last one primed, so the DS2480B pulls the bus up right
+    /// after it until the time is over.
+    fn write_command_with_pull_up(&mut self, command: u8, ns: u32) -> Result<(), Error<io::Error>> {
+        if !self.parasite_power {
+            self.write_command(command)?;
+            self.delay(ns);
+            return Ok(());
+        }
+        self.set_mode(Mode::Command)?;
+        let mut bits = [0; 8];
+        for (index, bit) in bits.iter_mut().enumerate() {
+            *bit = COMMAND_SINGLE_BIT | (command >> index & 0x01) << 4 | self.speed.bits();
+        }
+        bits[7] |= SINGLE_BIT_PRIME;
+        self.write(&bits)?;
+        self.read(&mut bits)?;
+        thread::sleep(Duration::from_nanos(ns as _));
+        self.write(&[COMMAND_PULSE_TERMINATE])?;
+        let mut response = [0];
+        self.read(&mut response)
+    }
+
+    /// Runs the search with the search accelerator.
+    ///
+    /// The 16 data bytes hold a pair of bits for each bit position: the
+    /// discrepancy flag and the direction. The DS2480B takes the direction at
+    /// the conflicts and returns the flags and the taken directions.
+    fn triplets(&mut self, directions: u64) -> Result<(u64, u64), Error<io::Error>> {
+        self.set_mode(Mode::Command)?;
+        self.write(&[COMMAND_SEARCH_ACCELERATOR_ON | self.speed.bits()])?;
+        let mut bytes = [0; 16];
+        for index in 0..u64::BITS {
+            if directions >> index & 0x01 == 0x01 {
+                bytes[index as usize / 4] |= 0b10 << (index % 4 * 2);
+            }
+        }
+        self.data(&mut bytes)?;
+        self.set_mode(Mode::Command)?;
+        self.write(&[COMMAND_SEARCH_ACCELERATOR_OFF | self.speed.bits()])?;
+        // Nothing answered, every bit and its complement were read as 1: the
+        // direction is always 1, with or without the discrepancy flag.
+        if bytes.iter().all(|byte| byte & 0xAA == 0xAA) {
+            return Ok((u64::MAX, u64::MAX));
+        }
+        let mut bits = 0;
+        let mut complements = 0;
+        for index in 0..u64::BITS {
+            let pair = bytes[index as usize / 4] >> (index % 4 * 2);
+            let discrepancy = pair & 0b01 == 0b01;
+            let direction = pair & 0b10 == 0b10;
+            // At a conflict the given direction is taken, a flagged 1 taken
+            // instead of a 0 is a bit and its complement read as 1: the
+            // devices have left the bus.
+            if discrepancy && direction && directions >> index & 0x01 == 0x00 {
+                return Err(self.report(Ds18b20Error::NoAttachedDevices.into()));
+            }
+            // A discrepancy reads as `0b00`, otherwise the direction is the
+            // only bit the devices have.
+            if !discrepancy {
+                bits |= (direction as u64) << index;
+                complements |= (!direction as u64) << index;
+            }
+        }
+        Ok((bits, complements))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RomCommands;
+    use std::{collections::VecDeque, vec::Vec};
+
+    /// Serial port stand-in, records the written bytes and returns the
+    /// prepared responses.
+    #[derive(Default)]
+    struct Port {
+        written: Vec<u8>,
+        responses: VecDeque<u8>,
+    }
+
+    impl Write for Port {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for Port {
+        fn read(&mut self, bytes: &mut [u8]) -> io::Result<usize> {
+            self.responses.read(bytes)
+        }
+    }
+
+    #[test]
+    fn operations() {
+        let port = Port {
+            responses: [0x3E, 0xCD, 0x28, 0x93].into(),
+            ..Default::default()
+        };
+        let mut ds2480b = Ds2480b::new(port).unwrap();
+        assert_eq!(Ok(true), ds2480b.initialization().map_err(|_| ()));
+        assert_eq!(Ok(0x28), ds2480b.read_byte().map_err(|_| ()));
+        assert_eq!(Ok(true), ds2480b.read_bit().map_err(|_| ()));
+        assert!(ds2480b.read_bit().is_err());
+        assert_eq!(
+            [0xC1, 0x3F, 0xC1, 0xE1, 0xFF, 0xE3, 0x91, 0x91],
+            ds2480b.into_inner().written[..],
+        );
+    }
+
+    #[test]
+    fn escape() {
+        let port = Port {
+            responses: [0x3E, 0xE3].into(),
+            ..Default::default()
+        };
+        let mut ds2480b = Ds2480b::new(port).unwrap();
+        ds2480b.write_byte(0xE3).unwrap();
+        assert_eq!(
+            [0xC1, 0x3F, 0xE1, 0xE3, 0xE3],
+            ds2480b.into_inner().written[..],
+        );
+    }
+
+    #[test]
+    fn no_device() {
+        let mut responses = std::vec![0x3E, 0xCD, 0xF0];
+        responses.extend([0xAA; 16]);
+        let port = Port {
+            responses: responses.into(),
+            ..Default::default()
+        };
+        let mut ds2480b = Ds2480b::new(port).unwrap();
+        assert_eq!(0, ds2480b.devices().count());
+    }
+
+    #[test]
+    fn device_left() {
+        // The device answers the first 32 bits, then nothing answers.
+        let mut responses = std::vec![0x3E, 0xCD, 0xF0];
+        responses.extend([0x00; 8]);
+        responses.extend([0xFF; 8]);
+        let port = Port {
+            responses: responses.into(),
+            ..Default::default()
+        };
+        let mut ds2480b = Ds2480b::new(port).unwrap();
+        let roms: Vec<_> = ds2480b.devices().collect();
+        assert!(matches!(
+            roms[..],
+            [Err(Error::Ds18b20(Ds18b20Error::NoAttachedDevices))]
+        ));
+    }
+
+    #[test]
+    fn strong_pull_up() {
+        let port = Port {
+            responses: [
+                0x3E, 0x44, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x76,
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let mut ds2480b = Ds2480b::new(port).unwrap();
+        ds2480b.write_command_with_pull_up(0x44, 0).unwrap();
+        ds2480b.set_parasite_power(true);
+        ds2480b.write_command_with_pull_up(0x44, 0).unwrap();
+        assert_eq!(
+            [0xC1, 0x3F, 0xE1, 0x44, 0xE3, 0x81, 0x81, 0x91, 0x81, 0x81, 0x81, 0x91, 0x83, 0xF1,],
+            ds2480b.into_inner().written[..],
+        );
+    }
+}
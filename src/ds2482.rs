@@ -0,0 +1,370 @@
+//! 1-Wire master on a DS2482-100/-800 I²C bridge.
+//!
+//! The bridge generates the 1-Wire time slots itself, so the ROM and memory
+//! commands only see the byte level operations and the triplet, which makes
+//! the search fast.
+
+use crate::{Ds18b20Error, Error, OneWireBus, Speed};
+use bitflags::bitflags;
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+
+/// Default I²C address (AD0 and AD1 low).
+pub const ADDRESS: u8 = 0x18;
+
+const COMMAND_DEVICE_RESET: u8 = 0xF0;
+const COMMAND_SET_READ_POINTER: u8 = 0xE1;
+const COMMAND_WRITE_CONFIGURATION: u8 = 0xD2;
+const COMMAND_CHANNEL_SELECT: u8 = 0xC3;
+const COMMAND_ONE_WIRE_RESET: u8 = 0xB4;
+const COMMAND_ONE_WIRE_SINGLE_BIT: u8 = 0x87;
+const COMMAND_ONE_WIRE_WRITE_BYTE: u8 = 0xA5;
+const COMMAND_ONE_WIRE_READ_BYTE: u8 = 0x96;
+const COMMAND_ONE_WIRE_TRIPLET: u8 = 0x78;
+
+const REGISTER_STATUS: u8 = 0xF0;
+const REGISTER_READ_DATA: u8 = 0xE1;
+
+/// Channel select codes, written / read back.
+const CHANNELS: [(u8, u8); 8] = [
+    (0xF0, 0xB8),
+    (0xE1, 0xB1),
+    (0xD2, 0xAA),
+    (0xC3, 0xA3),
+    (0xB4, 0x9C),
+    (0xA5, 0x95),
+    (0x96, 0x8E),
+    (0x87, 0x87),
+];
+
+/// Status register polls before giving up on a busy 1-Wire line.
+const POLLS: u32 = 500;
+/// Delay between status register polls.
+const POLL_DELAY_US: u32 = 10;
+
+bitflags! {
+    /// Configuration register
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct Configuration: u8 {
+        /// Active pull-up.
+        const APU = 0b0001;
+        /// Strong pull-up, for the next byte or bit.
+        const SPU = 0b0100;
+        /// 1-Wire speed, overdrive.
+        const ONE_WIRE_SPEED = 0b1000;
+    }
+}
+
+bitflags! {
+    /// Status register
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct Status: u8 {
+        /// 1-Wire busy.
+        const ONE_WIRE_BUSY = 0b0000_0001;
+        /// Presence pulse detected.
+        const PRESENCE_PULSE_DETECT = 0b0000_0010;
+        /// Short detected.
+        const SHORT_DETECTED = 0b0000_0100;
+        /// Logic level of the 1-Wire line.
+        const LOGIC_LEVEL = 0b0000_1000;
+        /// Device reset.
+        const DEVICE_RESET = 0b0001_0000;
+        /// Single bit result.
+        const SINGLE_BIT_RESULT = 0b0010_0000;
+        /// Triplet second bit.
+        const TRIPLET_SECOND_BIT = 0b0100_0000;
+        /// Branch direction taken.
+        const BRANCH_DIRECTION_TAKEN = 0b1000_0000;
+    }
+}
+
+/// DS2482 1-Wire master
+#[derive(Clone, Copy, Debug)]
+pub struct Ds2482<T, U> {
+    i2c: T,
+    delay: U,
+    address: u8,
+    /// Configuration register, without the strong pull-up.
+    configuration: Configuration,
+    parasite_power: bool,
+}
+
+impl<T, U> Ds2482<T, U> {
+    pub fn into_inner(self) -> (T, U) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<T: I2c, U: DelayNs> Ds2482<T, U> {
+    /// Resets the bridge at the default address.
+    pub fn new(i2c: T, delay: U) -> Result<Self, Error<T::Error>> {
+        Self::with_address(i2c, delay, ADDRESS)
+    }
+
+    /// Resets the bridge at the given address.
+    pub fn with_address(i2c: T, delay: U, address: u8) -> Result<Self, Error<T::Error>> {
+        let mut ds2482 = Self {
+            i2c,
+            delay,
+            address,
+            configuration: Configuration::empty(),
+            parasite_power: false,
+        };
+        ds2482.device_reset()?;
+        Ok(ds2482)
+    }
+
+    /// Device reset.
+    ///
+    /// Terminates any 1-Wire communication and resets the configuration and
+    /// the selected channel.
+    pub fn device_reset(&mut self) -> Result<Status, Error<T::Error>> {
+        self.write(&[COMMAND_DEVICE_RESET])?;
+        let mut read = [0];
+        self.read(&mut read)?;
+        let status = Status::from_bits_retain(read[0]);
+        if !status.contains(Status::DEVICE_RESET) {
+            Err(Ds18b20Error::UnexpectedResponse)?;
+        }
+        self.configuration = Configuration::empty();
+        Ok(status)
+    }
+
+    /// Write configuration.
+    ///
+    /// The strong pull-up is cleared by the bridge after the next 1-Wire
+    /// byte or bit, the other bits are kept until the next write.
+    pub fn write_configuration(
+        &mut self,
+        configuration: Configuration,
+    ) -> Result<(), Error<T::Error>> {
+        let bits = configuration.bits();
+        self.write(&[COMMAND_WRITE_CONFIGURATION, !bits << 4 | bits])?;
+        let mut read = [0];
+        self.read(&mut read)?;
+        if read[0] != bits {
+            Err(Ds18b20Error::UnexpectedResponse)?;
+        }
+        self.configuration = configuration.difference(Configuration::SPU);
+        Ok(())
+    }
+
+    /// Channel select (DS2482-800 only), channel 0 through 7.
+    pub fn select_channel(&mut self, channel: u8) -> Result<(), Error<T::Error>> {
+        let Some(&(code, expected)) = CHANNELS.get(channel as usize) else {
+            Err(Ds18b20Error::InvalidChannel { channel })?
+        };
+        self.write(&[COMMAND_CHANNEL_SELECT, code])?;
+        let mut read = [0];
+        self.read(&mut read)?;
+        if read[0] != expected {
+            Err(Ds18b20Error::UnexpectedResponse)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the status register.
+    pub fn status(&mut self) -> Result<Status, Error<T::Error>> {
+        let mut read = [0];
+        self.i2c
+            .write_read(
+                self.address,
+                &[COMMAND_SET_READ_POINTER, REGISTER_STATUS],
+                &mut read,
+            )
+            .map_err(Error::Bus)?;
+        Ok(Status::from_bits_retain(read[0]))
+    }
+
+    /// Waits for the 1-Wire command to finish and returns the status.
+    ///
+    /// The 1-Wire commands leave the read pointer at the status register.
+    fn wait(&mut self) -> Result<Status, Error<T::Error>> {
+        let mut read = [0];
+        for _ in 0..POLLS {
+            self.read(&mut read)?;
+            let status = Status::from_bits_retain(read[0]);
+            if !status.contains(Status::ONE_WIRE_BUSY) {
+                return Ok(status);
+            }
+            self.delay.delay_us(POLL_DELAY_US);
+        }
+        Err(Ds18b20Error::Timeout)?
+    }
+
+    /// Sends a 1-Wire command and waits for it to finish.
+    fn command(&mut self, bytes: &[u8]) -> Result<Status, Error<T::Error>> {
+        self.write(bytes)?;
+        self.wait()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error<T::Error>> {
+        self.i2c.write(self.address, bytes).map_err(Error::Bus)
+    }
+
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), Error<T::Error>> {
+        self.i2c.read(self.address, bytes).map_err(Error::Bus)
+    }
+}
+
+impl<T: I2c, U: DelayNs> OneWireBus for Ds2482<T, U> {
+    type Error = T::Error;
+
+    fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+        let status = self.command(&[COMMAND_ONE_WIRE_RESET])?;
+        if status.contains(Status::SHORT_DETECTED) {
+            Err(Ds18b20Error::BusNotHigh)?;
+        }
+        Ok(status.contains(Status::PRESENCE_PULSE_DETECT))
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+        let status = self.command(&[COMMAND_ONE_WIRE_SINGLE_BIT, 0x80])?;
+        Ok(status.contains(Status::SINGLE_BIT_RESULT))
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+        self.command(&[COMMAND_ONE_WIRE_SINGLE_BIT, if bit { 0x80 } else { 0x00 }])?;
+        Ok(())
+    }
+
+    fn delay(&mut self, ns: u32) {
+        self.delay.delay_ns(ns);
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error<T::Error>> {
+        self.command(&[COMMAND_ONE_WIRE_READ_BYTE])?;
+        self.write(&[COMMAND_SET_READ_POINTER, REGISTER_READ_DATA])?;
+        let mut read = [0];
+        self.read(&mut read)?;
+        Ok(read[0])
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error<T::Error>> {
+        self.command(&[COMMAND_ONE_WIRE_WRITE_BYTE, byte])?;
+        Ok(())
+    }
+
+    fn speed(&self) -> Speed {
+        if self.configuration.contains(Configuration::ONE_WIRE_SPEED) {
+            Speed::Overdrive
+        } else {
+            Speed::Standard
+        }
+    }
+
+    fn supports_speed(&self, _speed: Speed) -> bool {
+        true
+    }
+
+    fn set_speed(&mut self, speed: Speed) -> Result<(), Error<T::Error>> {
+        if speed != self.speed() {
+            let configuration = self.configuration ^ Configuration::ONE_WIRE_SPEED;
+            self.write_configuration(configuration)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the strong pull-up bit before the command, so the bridge pulls
+    /// the bus up right after its last bit, and clears it after the given
+    /// time.
+    fn write_command_with_pull_up(&mut self, command: u8, ns: u32) -> Result<(), Error<T::Error>> {
+        if !self.parasite_power {
+            self.write_command(command)?;
+            self.delay(ns);
+            return Ok(());
+        }
+        self.write_configuration(self.configuration | Configuration::SPU)?;
+        self.write_command(command)?;
+        self.delay(ns);
+        self.write_configuration(self.configuration)
+    }
+
+    fn triplet(&mut self, direction: bool) -> Result<(bool, bool, bool), Error<T::Error>> {
+        let status = self.command(&[
+            COMMAND_ONE_WIRE_TRIPLET,
+            if direction { 0x80 } else { 0x00 },
+        ])?;
+        Ok((
+            status.contains(Status::SINGLE_BIT_RESULT),
+            status.contains(Status::TRIPLET_SECOND_BIT),
+            status.contains(Status::BRANCH_DIRECTION_TAKEN),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use embedded_hal_mock::eh1::{
+        delay::NoopDelay,
+        i2c::{Mock, Transaction},
+    };
+    use std::vec;
+
+    #[test]
+    fn operations() {
+        let expectations = [
+            // Device reset.
+            Transaction::write(ADDRESS, vec![COMMAND_DEVICE_RESET]),
+            Transaction::read(ADDRESS, vec![0x18]),
+            // 1-Wire reset, busy once.
+            Transaction::write(ADDRESS, vec![COMMAND_ONE_WIRE_RESET]),
+            Transaction::read(ADDRESS, vec![0x19]),
+            Transaction::read(ADDRESS, vec![0x1A]),
+            // 1-Wire read byte.
+            Transaction::write(ADDRESS, vec![COMMAND_ONE_WIRE_READ_BYTE]),
+            Transaction::read(ADDRESS, vec![0x18]),
+            Transaction::write(ADDRESS, vec![COMMAND_SET_READ_POINTER, REGISTER_READ_DATA]),
+            Transaction::read(ADDRESS, vec![0x28]),
+            // 1-Wire triplet.
+            Transaction::write(ADDRESS, vec![COMMAND_ONE_WIRE_TRIPLET, 0x80]),
+            Transaction::read(ADDRESS, vec![0x98]),
+        ];
+        let mut i2c = Mock::new(&expectations);
+        let mut ds2482 = Ds2482::new(i2c.clone(), NoopDelay).unwrap();
+        assert_eq!(Ok(true), ds2482.initialization());
+        assert_eq!(Ok(0x28), ds2482.read_byte());
+        assert_eq!(Ok((false, false, true)), ds2482.triplet(true));
+        i2c.done();
+    }
+
+    #[test]
+    fn configuration() {
+        let expectations = [
+            Transaction::write(ADDRESS, vec![COMMAND_DEVICE_RESET]),
+            Transaction::read(ADDRESS, vec![0x18]),
+            // Overdrive.
+            Transaction::write(ADDRESS, vec![COMMAND_WRITE_CONFIGURATION, 0x78]),
+            Transaction::read(ADDRESS, vec![0x08]),
+            // Convert T with the strong pull-up, the speed is kept.
+            Transaction::write(ADDRESS, vec![COMMAND_WRITE_CONFIGURATION, 0x3C]),
+            Transaction::read(ADDRESS, vec![0x0C]),
+            Transaction::write(ADDRESS, vec![COMMAND_ONE_WIRE_WRITE_BYTE, 0x44]),
+            Transaction::read(ADDRESS, vec![0x18]),
+            Transaction::write(ADDRESS, vec![COMMAND_WRITE_CONFIGURATION, 0x78]),
+            Transaction::read(ADDRESS, vec![0x08]),
+        ];
+        let mut i2c = Mock::new(&expectations);
+        let mut ds2482 = Ds2482::new(i2c.clone(), NoopDelay).unwrap();
+        assert_eq!(
+            Err(Error::Ds18b20(Ds18b20Error::InvalidChannel { channel: 8 })),
+            ds2482.select_channel(8)
+        );
+        ds2482.set_speed(Speed::Overdrive).unwrap();
+        ds2482.set_speed(Speed::Overdrive).unwrap();
+        assert_eq!(Speed::Overdrive, ds2482.speed());
+        ds2482.set_parasite_power(true);
+        ds2482.write_command_with_pull_up(0x44, 10_000_000).unwrap();
+        i2c.done();
+    }
+}
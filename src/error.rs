@@ -1,6 +1,6 @@
 use crate::{
     scratchpad::{ELEVEN, NINE, TEN, TWELVE},
-    FAMILY_CODE,
+    Speed, Temperature, FAMILY_CODE,
 };
 use thiserror::Error;
 
@@ -13,6 +13,8 @@ pub enum Error<T> {
     #[error(transparent)]
     Pin(T),
     #[error(transparent)]
+    Bus(T),
+    #[error(transparent)]
     Ds18b20(#[from] Ds18b20Error),
 }
 
@@ -25,10 +27,55 @@ pub enum Ds18b20Error {
     NoAttachedDevices,
     #[error("timeout expired")]
     Timeout,
+    #[error("unexpected response, a device may have been removed from the bus")]
+    UnexpectedResponse,
     #[error("unexpected CRC {{ crc={crc}, expected={expected} }}")]
     UnexpectedCrc { crc: u8, expected: u8 },
     #[error("unexpected family code {{ family_code={family_code}, expected={FAMILY_CODE} }}")]
     UnexpectedFamilyCode { family_code: u8 },
     #[error("unexpected configuration register {{ configuration_register={configuration_register:b}, expected=[{NINE:b}, {TEN:b}, {ELEVEN:b}, {TWELVE:b}] }}")]
     UnexpectedConfigurationRegister { configuration_register: u8 },
+    #[error("no device responded, all bytes were read as 0xFF")]
+    NoResponse,
+    #[error("all bits were read as 0, the bus may be stuck low or slow to rise")]
+    AllZeros,
+    #[error("the temperature is the power-on reset value (85°C), it has not been converted since power-up")]
+    PowerOnReset,
+    #[error(
+        "temperature out of range {{ temperature={temperature}, expected=[{}, {}] }}",
+        Temperature::MIN,
+        Temperature::MAX
+    )]
+    OutOfRange { temperature: Temperature },
+    #[error("unsupported speed {{ speed={speed:?} }}")]
+    UnsupportedSpeed { speed: Speed },
+    #[error("invalid channel {{ channel={channel}, expected=[0, 7] }}")]
+    InvalidChannel { channel: u8 },
+    #[error("the server returned an error {{ ret={ret} }}")]
+    ServerError { ret: i32 },
+}
+
+/// Configuration error
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ConfigurationError {
+    #[error("{field} too short {{ value={value} ns, min={min} ns, speed={speed:?} }}")]
+    TooShort {
+        field: &'static str,
+        value: u32,
+        min: u32,
+        speed: Speed,
+    },
+    #[error("{field} too long {{ value={value} ns, max={max} ns, speed={speed:?} }}")]
+    TooLong {
+        field: &'static str,
+        value: u32,
+        max: u32,
+        speed: Speed,
+    },
+    #[error("SPI clock too slow for {field} {{ frequency={frequency} Hz, min={min} Hz }}")]
+    ClockTooSlow {
+        field: &'static str,
+        frequency: u32,
+        min: u32,
+    },
 }
@@ -1,27 +1,43 @@
 //! Implementation of the 1-Wire protocol.
 
 #![no_std]
-#![feature(error_in_core)]
+
+#[cfg(feature = "std")]
+extern crate std;
 
 pub use self::{
-    commands::{memory::MemoryCommands, rom::RomCommands},
-    configuration::Configuration,
+    bus::OneWireBus,
+    commands::{
+        memory::{MemoryCommands, PowerSupply},
+        rom::{Devices, RomCommands, SearchState},
+    },
+    configuration::{Configuration, ConfigurationBuilder, Preset, Speed},
     error::Error,
+    observer::{BusObserver, Counters, NoObserver, Statistics},
+    pull_up::{ActiveHigh, ActiveLow, NoStrongPullUp, StrongPullUp},
+    retry::{Retried, RetryPolicy, Retryable},
     rom::Rom,
     scratchpad::Scratchpad,
+    symptom::{detect_clone_symptom, CloneSymptom},
+    temperature::Temperature,
+    transactions::{Temperatures, Transaction},
 };
 
+use commands::memory::convert;
 use embedded_hal::{
     delay::DelayNs,
     digital::{ErrorType, InputPin, OutputPin},
 };
 use error::Ds18b20Error;
+use scratchpad::{Resolution, Triggers};
 
 pub const FAMILY_CODE: u8 = 0x28;
 
 /// Ds18b20
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Ds18b20 {
     rom: Rom,
+    resolution: Resolution,
 }
 
 impl Ds18b20 {
@@ -29,7 +45,10 @@ impl Ds18b20 {
     /// configuration data, then returns a device
     pub fn new(rom: Rom) -> Result<Ds18b20, Ds18b20Error> {
         match rom.family_code {
-            FAMILY_CODE => Ok(Self { rom }),
+            FAMILY_CODE => Ok(Self {
+                rom,
+                resolution: Default::default(),
+            }),
             _ => Err(Ds18b20Error::UnexpectedFamilyCode {
                 family_code: rom.family_code,
             }),
@@ -40,32 +59,230 @@ impl Ds18b20 {
     pub fn rom(&self) -> &Rom {
         &self.rom
     }
+
+    /// Returns the last known device resolution
+    ///
+    /// It is the power-on default (12 bits) until the resolution is written or
+    /// recalled from EEPROM.
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+}
+
+/// Device operations
+///
+/// Each operation is a complete transaction: initialization, match ROM and the
+/// memory function command.
+impl Ds18b20 {
+    /// Begins a temperature conversion.
+    ///
+    /// The temperature can be read after [`Resolution::conversion_time`]. With
+    /// parasite-powered devices on the bus the strong pullup is held for the
+    /// conversion time, so the conversion is complete on return.
+    pub fn start_conversion<B: OneWireBus>(&self, bus: &mut B) -> Result<(), Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        convert(bus, self.resolution.conversion_time())
+    }
+
+    /// Reads the scratchpad.
+    pub fn read_scratchpad<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Scratchpad, Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        bus.read_scratchpad()
+    }
+
+    /// Reads the temperature measured by the last conversion.
+    ///
+    /// The power-on reset value and the values out of the measurement range
+    /// are reported as errors, see [`Scratchpad::measured_temperature`].
+    pub fn read_temperature<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Temperature, Error<B::Error>> {
+        let scratchpad = self.read_scratchpad(bus)?;
+        scratchpad
+            .measured_temperature()
+            .map_err(|error| bus.report(error.into()))
+    }
+
+    /// Writes the resolution, keeping the temperature triggers.
+    ///
+    /// The value is lost on power down unless saved to EEPROM.
+    pub fn set_resolution<B: OneWireBus>(
+        &mut self,
+        bus: &mut B,
+        resolution: Resolution,
+    ) -> Result<(), Error<B::Error>> {
+        let mut scratchpad = self.read_scratchpad(bus)?;
+        scratchpad.configuration_register.resolution = resolution;
+        self.write_scratchpad(bus, scratchpad)
+    }
+
+    /// Writes the temperature triggers (TH and TL), keeping the resolution.
+    ///
+    /// The values are lost on power down unless saved to EEPROM.
+    pub fn set_alarm_thresholds<B: OneWireBus>(
+        &mut self,
+        bus: &mut B,
+        triggers: Triggers,
+    ) -> Result<(), Error<B::Error>> {
+        let mut scratchpad = self.read_scratchpad(bus)?;
+        scratchpad.triggers = triggers;
+        self.write_scratchpad(bus, scratchpad)
+    }
+
+    /// Writes the temperature triggers and the configuration register.
+    ///
+    /// The written resolution becomes the last known one.
+    pub fn write_scratchpad<B: OneWireBus>(
+        &mut self,
+        bus: &mut B,
+        scratchpad: Scratchpad,
+    ) -> Result<(), Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        bus.write_scratchpad(scratchpad)?;
+        self.resolution = scratchpad.configuration_register.resolution;
+        Ok(())
+    }
+
+    /// Saves the temperature triggers and the configuration register to
+    /// EEPROM.
+    pub fn save_to_eeprom<B: OneWireBus>(&self, bus: &mut B) -> Result<(), Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        bus.copy_scratchpad()
+    }
+
+    /// Loads the temperature triggers and the configuration register from
+    /// EEPROM, then reads back the recalled resolution.
+    pub fn recall_from_eeprom<B: OneWireBus>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<(), Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        bus.recall_eeprom()?;
+        self.resolution = self.read_scratchpad(bus)?.configuration_register.resolution;
+        Ok(())
+    }
+
+    /// Reads the power supply mode.
+    pub fn read_power_supply<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+    ) -> Result<PowerSupply, Error<B::Error>> {
+        self.transaction().begin(bus)?;
+        bus.read_power_supply()
+    }
+
+    pub(crate) fn transaction(&self) -> Transaction {
+        Transaction {
+            rom: Some(self.rom),
+        }
+    }
 }
 
 /// Ds18b20 driver
 #[derive(Clone, Copy, Debug, Default)]
-pub struct Driver<T, U> {
+pub struct Driver<T, U, V = NoStrongPullUp, W = NoObserver> {
     pin: T,
     delay: U,
-    configuration: Configuration,
+    standard: Configuration,
+    overdrive: Configuration,
+    speed: Speed,
+    pull_up: V,
+    parasite_power: bool,
+    observer: W,
 }
 
-impl<T, U> Driver<T, U> {
+impl<T, U, V, W> Driver<T, U, V, W> {
+    /// Returns the configuration of the current speed.
     pub fn configuration(&self) -> &Configuration {
-        &self.configuration
+        self.configuration_for(self.speed)
     }
 
     pub fn configuration_mut(&mut self) -> &mut Configuration {
-        &mut self.configuration
+        self.configuration_for_mut(self.speed)
+    }
+
+    /// Returns the configuration used at the speed.
+    pub fn configuration_for(&self, speed: Speed) -> &Configuration {
+        match speed {
+            Speed::Standard => &self.standard,
+            Speed::Overdrive => &self.overdrive,
+        }
+    }
+
+    pub fn configuration_for_mut(&mut self, speed: Speed) -> &mut Configuration {
+        match speed {
+            Speed::Standard => &mut self.standard,
+            Speed::Overdrive => &mut self.overdrive,
+        }
+    }
+
+    /// Returns the speed of the following operations.
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    /// Uses the strong pull-up after the temperature conversion and the copy
+    /// scratchpad commands, when parasite-powered devices are on the bus.
+    pub fn with_strong_pull_up<X>(self, pull_up: X) -> Driver<T, U, X, W> {
+        Driver {
+            pin: self.pin,
+            delay: self.delay,
+            standard: self.standard,
+            overdrive: self.overdrive,
+            speed: self.speed,
+            pull_up,
+            parasite_power: self.parasite_power,
+            observer: self.observer,
+        }
+    }
+
+    /// Calls the observer on the protocol events.
+    pub fn with_observer<X>(self, observer: X) -> Driver<T, U, V, X> {
+        Driver {
+            pin: self.pin,
+            delay: self.delay,
+            standard: self.standard,
+            overdrive: self.overdrive,
+            speed: self.speed,
+            pull_up: self.pull_up,
+            parasite_power: self.parasite_power,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &W {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut W {
+        &mut self.observer
+    }
+
+    /// Returns `true` if parasite-powered devices are on the bus.
+    ///
+    /// It is set once [`read_power_supply`](MemoryCommands::read_power_supply)
+    /// reports a parasite-powered device, and updated by
+    /// [`read_power_supply_all`](MemoryCommands::read_power_supply_all).
+    pub fn parasite_power(&self) -> bool {
+        self.parasite_power
     }
 }
 
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
+impl<T: InputPin + OutputPin + ErrorType, U> Driver<T, U> {
     pub fn new(pin: T, delay: U) -> Result<Self, Error<T::Error>> {
         let mut driver = Self {
             pin,
             delay,
-            configuration: Default::default(),
+            standard: Configuration::standard(),
+            overdrive: Configuration::overdrive(),
+            speed: Speed::Standard,
+            pull_up: NoStrongPullUp,
+            parasite_power: false,
+            observer: NoObserver,
         };
         // Pin should be high during idle.
         driver.set_high()?;
@@ -74,7 +291,7 @@ impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
 }
 
 /// Basic input pin operations
-impl<T: InputPin + ErrorType, U> Driver<T, U> {
+impl<T: InputPin + ErrorType, U, V, W> Driver<T, U, V, W> {
     pub fn is_high(&mut self) -> Result<bool, Error<T::Error>> {
         self.pin.is_high().map_err(Error::Pin)
     }
@@ -85,7 +302,7 @@ impl<T: InputPin + ErrorType, U> Driver<T, U> {
 }
 
 /// Basic output pin operations
-impl<T: OutputPin + ErrorType, U> Driver<T, U> {
+impl<T: OutputPin + ErrorType, U, V, W> Driver<T, U, V, W> {
     /// Set the output as high.
     ///
     /// Disconnects the bus, letting another device (or the pull-up resistor)
@@ -100,87 +317,128 @@ impl<T: OutputPin + ErrorType, U> Driver<T, U> {
 }
 
 /// Basic delay operations
-impl<T, U: DelayNs> Driver<T, U> {
-    pub fn delay(&mut self, ns: u32) {
+impl<T, U: DelayNs, V, W> Driver<T, U, V, W> {
+    pub(crate) fn delay_ns(&mut self, ns: u32) {
         self.delay.delay_ns(ns);
     }
 }
 
 /// Bit operations
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
-    /// Read a bit from the 1-Wire bus and return it. Provide 10us recovery
-    /// time.
-    pub fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+impl<T: InputPin + OutputPin + ErrorType, U: DelayNs, V, W> Driver<T, U, V, W> {
+    /// Read time slot, the bit is returned. Provide 10us recovery time.
+    ///
+    /// Not observed, see [`OneWireBus::read_bit`].
+    pub(crate) fn read_slot(&mut self) -> Result<bool, Error<T::Error>> {
         self.set_low()?;
-        self.delay(self.configuration.a);
+        self.delay_ns(self.configuration().a);
         self.set_high()?;
-        self.delay(self.configuration.e);
+        self.delay_ns(self.configuration().e);
         let bit = self.is_high()?;
-        self.delay(self.configuration.f);
+        self.delay_ns(self.configuration().f);
         Ok(bit)
     }
 
-    /// Send a 1-Wire write bit. Provide 10us recovery time.
-    pub fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+    /// Write time slot. Provide 10us recovery time.
+    ///
+    /// Not observed, see [`OneWireBus::write_bit`].
+    pub(crate) fn write_slot(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
         self.set_low()?;
-        self.delay(if bit {
-            self.configuration.a
+        self.delay_ns(if bit {
+            self.configuration().a
         } else {
-            self.configuration.c
+            self.configuration().c
         });
         self.set_high()?;
-        self.delay(if bit {
-            self.configuration.b
+        self.delay_ns(if bit {
+            self.configuration().b
         } else {
-            self.configuration.d
+            self.configuration().d
         });
         Ok(())
     }
 }
 
+/// Strong pull-up operations
+impl<T, U, V, W> Driver<T, U, V, W>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: DelayNs,
+    V: StrongPullUp<T::Error>,
+{
+    /// Write time slot, then supply parasite-powered devices through
+    /// the strong pull-up for the given time (ns).
+    ///
+    /// The pull-up is enabled as soon as the bus is released, the recovery
+    /// time is part of the given time.
+    pub(crate) fn write_slot_with_pull_up(
+        &mut self,
+        bit: bool,
+        ns: u32,
+    ) -> Result<(), Error<T::Error>> {
+        self.set_low()?;
+        self.delay_ns(if bit {
+            self.configuration().a
+        } else {
+            self.configuration().c
+        });
+        self.set_high()?;
+        self.pull_up.enable().map_err(Error::Pin)?;
+        self.delay_ns(ns);
+        self.pull_up.disable().map_err(Error::Pin)
+    }
+}
+
 /// Byte operations
-impl<T: InputPin + OutputPin + ErrorType, U: DelayNs> Driver<T, U> {
+impl<T, U, V, W> Driver<T, U, V, W>
+where
+    T: InputPin + OutputPin + ErrorType,
+    U: DelayNs,
+    V: StrongPullUp<T::Error>,
+    W: BusObserver,
+{
     /// Read 1-Wire data byte.
     pub fn read_byte(&mut self) -> Result<u8, Error<T::Error>> {
-        let mut byte = 0;
-        for _ in 0..u8::BITS {
-            byte >>= 1;
-            if self.read_bit()? {
-                byte |= 0x80;
-            }
-        }
-        Ok(byte)
+        OneWireBus::read_byte(self)
     }
 
     pub fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), Error<T::Error>> {
-        for byte in bytes {
-            *byte = self.read_byte()?;
-        }
-        Ok(())
+        OneWireBus::read_bytes(self, bytes)
     }
 
     /// Write 1-Wire data byte.
-    pub fn write_byte(&mut self, mut byte: u8) -> Result<(), Error<T::Error>> {
-        for _ in 0..u8::BITS {
-            self.write_bit(byte & 0x01 == 0x01)?;
-            byte >>= 1;
-        }
-        Ok(())
+    pub fn write_byte(&mut self, byte: u8) -> Result<(), Error<T::Error>> {
+        OneWireBus::write_byte(self, byte)
     }
 
     pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<T::Error>> {
-        for byte in bytes {
-            self.write_byte(*byte)?;
-        }
-        Ok(())
+        OneWireBus::write_bytes(self, bytes)
     }
 }
 
+pub mod asynch;
 pub mod commands;
 pub mod crc8;
+#[cfg(feature = "std")]
+pub mod ds2480b;
+pub mod ds2482;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod owserver;
 pub mod scratchpad;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod spi;
+pub mod trace;
+pub mod uart;
+#[cfg(feature = "std")]
+pub mod w1;
 
+mod bus;
 mod configuration;
+mod observer;
+mod pull_up;
+mod retry;
 mod rom;
+mod symptom;
+mod temperature;
 mod transactions;
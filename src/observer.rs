@@ -0,0 +1,298 @@
+use crate::{
+    commands::rom::{COMMAND_ROM_MATCH, COMMAND_ROM_OVERDRIVE_MATCH, COMMAND_ROM_READ},
+    Ds18b20Error, Error, Rom,
+};
+
+/// Bus observer
+///
+/// Called by the [`Driver`](crate::Driver) on the protocol events, e.g. to
+/// collect statistics. All methods do nothing by default.
+pub trait BusObserver {
+    /// Reset pulse, `presence` is `true` if a presence pulse was detected.
+    fn reset(&mut self, _presence: bool) {}
+
+    /// ROM or function command sent.
+    fn command(&mut self, _command: u8) {}
+
+    /// Byte written, other than a command.
+    fn write(&mut self, _byte: u8) {}
+
+    /// Byte read.
+    fn read(&mut self, _byte: u8) {}
+
+    /// Error returned by an operation on the bus.
+    fn error<E>(&mut self, _error: &Error<E>) {}
+
+    /// Operation retried after the error, see
+    /// [`RetryPolicy`](crate::RetryPolicy).
+    fn retry<E>(&mut self, _error: &Error<E>) {}
+}
+
+impl<T: BusObserver> BusObserver for &mut T {
+    fn reset(&mut self, presence: bool) {
+        T::reset(self, presence)
+    }
+
+    fn command(&mut self, command: u8) {
+        T::command(self, command)
+    }
+
+    fn write(&mut self, byte: u8) {
+        T::write(self, byte)
+    }
+
+    fn read(&mut self, byte: u8) {
+        T::read(self, byte)
+    }
+
+    fn error<E>(&mut self, error: &Error<E>) {
+        T::error(self, error)
+    }
+
+    fn retry<E>(&mut self, error: &Error<E>) {
+        T::retry(self, error)
+    }
+}
+
+/// No observer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoObserver;
+
+impl BusObserver for NoObserver {}
+
+/// Counters
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Counters {
+    pub commands: u32,
+    pub bytes_written: u32,
+    pub bytes_read: u32,
+    /// All errors, including the CRC errors.
+    pub errors: u32,
+    pub crc_errors: u32,
+    pub retries: u32,
+}
+
+/// Statistics
+///
+/// Counts the events per device, in a table of up to `N` devices. A device is
+/// known from its ROM, sent by a match ROM command or read by a read ROM
+/// command. The events of the other commands (skip ROM, search) and of the
+/// devices which don't fit in the table go to the bus counters.
+#[derive(Clone, Copy, Debug)]
+pub struct Statistics<const N: usize> {
+    pub resets: u32,
+    pub presence_failures: u32,
+    bus: Counters,
+    devices: [Option<(Rom, Counters)>; N],
+    /// The device the following events belong to.
+    device: Option<usize>,
+    /// The ROM command and the ROM bytes being written or read.
+    rom: Option<(u8, [u8; 8], usize)>,
+}
+
+impl<const N: usize> Statistics<N> {
+    pub const fn new() -> Self {
+        Self {
+            resets: 0,
+            presence_failures: 0,
+            bus: Counters {
+                commands: 0,
+                bytes_written: 0,
+                bytes_read: 0,
+                errors: 0,
+                crc_errors: 0,
+                retries: 0,
+            },
+            devices: [None; N],
+            device: None,
+            rom: None,
+        }
+    }
+
+    /// Returns the counters of the events not attributed to a device.
+    pub fn bus(&self) -> &Counters {
+        &self.bus
+    }
+
+    /// Returns the counters of the device.
+    pub fn device(&self, rom: &Rom) -> Option<&Counters> {
+        self.devices()
+            .find(|(device, _)| *device == rom)
+            .map(|(_, counters)| counters)
+    }
+
+    /// Returns the devices and their counters.
+    pub fn devices(&self) -> impl Iterator<Item = (&Rom, &Counters)> {
+        self.devices
+            .iter()
+            .flatten()
+            .map(|(rom, counters)| (rom, counters))
+    }
+
+    fn counters(&mut self) -> &mut Counters {
+        match self.device {
+            Some(index) => &mut self.devices[index].as_mut().unwrap().1,
+            None => &mut self.bus,
+        }
+    }
+
+    /// Collects the ROM bytes, then selects the device.
+    fn rom(&mut self, byte: u8) {
+        let Some((_, bytes, len)) = &mut self.rom else {
+            return;
+        };
+        bytes[*len] = byte;
+        *len += 1;
+        if *len < bytes.len() {
+            return;
+        }
+        let bytes = *bytes;
+        self.device = Rom::try_from(bytes).ok().and_then(|rom| {
+            let index = self
+                .devices
+                .iter()
+                .position(|device| device.is_some_and(|(device, _)| device == rom))
+                .or_else(|| self.devices.iter().position(Option::is_none))?;
+            self.devices[index].get_or_insert((rom, Counters::default()));
+            Some(index)
+        });
+        self.flush();
+    }
+
+    /// Counts the ROM command and the ROM bytes, for the selected device.
+    fn flush(&mut self) {
+        let Some((command, _, len)) = self.rom.take() else {
+            return;
+        };
+        let counters = self.counters();
+        counters.commands += 1;
+        match command {
+            COMMAND_ROM_READ => counters.bytes_read += len as u32,
+            _ => counters.bytes_written += len as u32,
+        }
+    }
+}
+
+impl<const N: usize> Default for Statistics<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> BusObserver for Statistics<N> {
+    fn reset(&mut self, presence: bool) {
+        self.resets += 1;
+        if !presence {
+            self.presence_failures += 1;
+        }
+        self.flush();
+        self.device = None;
+    }
+
+    fn command(&mut self, command: u8) {
+        if let COMMAND_ROM_MATCH | COMMAND_ROM_OVERDRIVE_MATCH | COMMAND_ROM_READ = command {
+            self.rom = Some((command, [0; 8], 0));
+        } else {
+            self.counters().commands += 1;
+        }
+    }
+
+    fn write(&mut self, byte: u8) {
+        match self.rom {
+            Some(_) => self.rom(byte),
+            None => self.counters().bytes_written += 1,
+        }
+    }
+
+    fn read(&mut self, byte: u8) {
+        match self.rom {
+            Some(_) => self.rom(byte),
+            None => self.counters().bytes_read += 1,
+        }
+    }
+
+    fn error<E>(&mut self, error: &Error<E>) {
+        self.flush();
+        let counters = self.counters();
+        counters.errors += 1;
+        if let Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. }) = error {
+            counters.crc_errors += 1;
+        }
+    }
+
+    fn retry<E>(&mut self, _error: &Error<E>) {
+        self.counters().retries += 1;
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod test {
+    use super::*;
+    use crate::{
+        simulator::{
+            testing::{bus, device, noisy},
+            Delay, Faults, Pin,
+        },
+        Driver, Ds18b20, OneWireBus, RomCommands,
+    };
+
+    #[test]
+    fn statistics() {
+        let devices = [device(0x6F), device(0x01)];
+        let bus = bus(&devices, noisy());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus))
+            .unwrap()
+            .with_observer(Statistics::<4>::new());
+        let mut crc_errors = [0; 2];
+        for _ in 0..50 {
+            for (device, crc_errors) in devices.iter().zip(&mut crc_errors) {
+                let ds18b20 = Ds18b20::new(*device.rom()).unwrap();
+                match ds18b20.read_scratchpad(&mut driver) {
+                    Ok(_) => {}
+                    Err(Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. })) => *crc_errors += 1,
+                    Err(error) => panic!("{error:?}"),
+                }
+            }
+        }
+        let statistics = driver.observer();
+        assert_eq!(100, statistics.resets);
+        assert_eq!(0, statistics.presence_failures);
+        assert_eq!(&Counters::default(), statistics.bus());
+        assert_eq!(2, statistics.devices().count());
+        for (device, crc_errors) in devices.iter().zip(crc_errors) {
+            let counters = statistics.device(device.rom()).unwrap();
+            assert_ne!(0, crc_errors);
+            assert_eq!(
+                &Counters {
+                    // match ROM and read scratchpad
+                    commands: 100,
+                    bytes_written: 400,
+                    bytes_read: 450,
+                    errors: crc_errors,
+                    crc_errors,
+                    retries: 0,
+                },
+                counters
+            );
+        }
+
+        // With several devices the ROM read is garbled, so it is not attributed
+        // to a device.
+        bus.borrow_mut().set_faults(Faults::default());
+        let mut statistics = Statistics::<4>::new();
+        let mut driver = driver.with_observer(&mut statistics);
+        assert!(driver.initialization().unwrap());
+        assert!(driver.read_rom().is_err());
+        assert_eq!(0, statistics.devices().count());
+        assert_eq!(
+            Counters {
+                commands: 1,
+                bytes_read: 8,
+                errors: 1,
+                crc_errors: 1,
+                ..Default::default()
+            },
+            *statistics.bus()
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! DS18B20s behind an `owserver` (OWFS) over TCP.
+//!
+//! Every request is a 24-byte header (version, payload length, message type,
+//! control flags, size, offset; big-endian 32-bit integers) followed by the
+//! payload: the null-terminated path, then the data for a write. Every
+//! response is a header (version, payload length, return value, control
+//! flags, size, offset) followed by the payload. A negative return value is an
+//! error number.
+//!
+//! The server closes the connection after each request, so a new connection is
+//! opened for every request.
+
+use crate::{
+    crc8::calculate,
+    scratchpad::{Resolution, Triggers},
+    Ds18b20Error, Error, PowerSupply, Rom, Temperature, FAMILY_CODE,
+};
+use std::{
+    format,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The default owserver port.
+pub const PORT: u16 = 4304;
+
+const MESSAGE_READ: i32 = 2;
+const MESSAGE_WRITE: i32 = 3;
+const MESSAGE_DIR: i32 = 4;
+const MESSAGE_PRESENCE: i32 = 6;
+
+/// Control flags: temperatures in Celsius, device names as `28.0316A2791DFF`.
+const FLAGS: i32 = 0;
+/// The largest value read.
+const SIZE: i32 = 8192;
+/// The payload length of a keep-alive response, sent while the server is busy.
+const PING: i32 = -1;
+/// The largest payload accepted beyond the requested size, e.g. a directory
+/// entry.
+const MARGIN: i32 = 256;
+/// The return value of a missing device or property (`-ENOENT`).
+const ENOENT: i32 = -2;
+
+/// owserver client
+#[derive(Clone, Copy, Debug)]
+pub struct Owserver {
+    address: SocketAddr,
+}
+
+impl Owserver {
+    pub fn new(address: SocketAddr) -> Self {
+        Self { address }
+    }
+
+    /// Lists the entries of the directory, e.g. `/28.0316A2791DFF`.
+    pub fn dir(&self, path: &str) -> Result<Vec<String>, Error<io::Error>> {
+        let mut stream = self.request(MESSAGE_DIR, path, &[], 0)?;
+        // One response per entry, until an empty one.
+        let mut entries = Vec::new();
+        while let Some(payload) = response(&mut stream, 0)? {
+            if payload.is_empty() {
+                break;
+            }
+            entries.push(to_string(&payload)?);
+        }
+        Ok(entries)
+    }
+
+    /// Reads the value of the property.
+    pub fn read(&self, path: &str) -> Result<String, Error<io::Error>> {
+        let mut stream = self.request(MESSAGE_READ, path, &[], SIZE)?;
+        let payload = response(&mut stream, SIZE)?.unwrap_or_default();
+        to_string(&payload)
+    }
+
+    /// Writes the value of the property.
+    pub fn write(&self, path: &str, value: &str) -> Result<(), Error<io::Error>> {
+        let mut stream = self.request(MESSAGE_WRITE, path, value.as_bytes(), value.len() as _)?;
+        response(&mut stream, 0)?;
+        Ok(())
+    }
+
+    /// Returns `true` if the device is on the bus.
+    pub fn presence(&self, path: &str) -> Result<bool, Error<io::Error>> {
+        let mut stream = self.request(MESSAGE_PRESENCE, path, &[], 0)?;
+        match response(&mut stream, 0) {
+            Ok(_) => Ok(true),
+            Err(Error::Ds18b20(Ds18b20Error::ServerError { ret: ENOENT })) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the ROMs of the DS18B20s.
+    pub fn devices(&self) -> Result<Vec<Rom>, Error<io::Error>> {
+        Ok(self
+            .dir("/")?
+            .iter()
+            .filter_map(|entry| parse_rom(entry))
+            .filter(|rom| rom.family_code == FAMILY_CODE)
+            .collect())
+    }
+
+    /// Converts and reads the temperature at the given resolution, see
+    /// [`Temperature::check`].
+    pub fn read_temperature(
+        &self,
+        rom: &Rom,
+        resolution: Resolution,
+    ) -> Result<Temperature, Error<io::Error>> {
+        let bits = match resolution {
+            Resolution::Nine => 9,
+            Resolution::Ten => 10,
+            Resolution::Eleven => 11,
+            Resolution::Twelve => 12,
+        };
+        let celsius: f32 = parse(&self.read(&format!("{}/temperature{bits}", path(rom)))?)?;
+        let temperature = Temperature::from_raw((celsius * 16.0).round() as i16);
+        Ok(temperature.check()?)
+    }
+
+    pub fn read_power_supply(&self, rom: &Rom) -> Result<PowerSupply, Error<io::Error>> {
+        match parse::<u8>(&self.read(&format!("{}/power", path(rom)))?)? {
+            0 => Ok(PowerSupply::Parasite),
+            1 => Ok(PowerSupply::External),
+            _ => Err(Ds18b20Error::UnexpectedResponse)?,
+        }
+    }
+
+    /// Reads the temperature triggers.
+    pub fn triggers(&self, rom: &Rom) -> Result<Triggers, Error<io::Error>> {
+        Ok(Triggers {
+            high: parse(&self.read(&format!("{}/temphigh", path(rom)))?)?,
+            low: parse(&self.read(&format!("{}/templow", path(rom)))?)?,
+        })
+    }
+
+    /// Writes the temperature triggers, owserver saves them to EEPROM.
+    pub fn set_triggers(&self, rom: &Rom, triggers: Triggers) -> Result<(), Error<io::Error>> {
+        self.write(
+            &format!("{}/temphigh", path(rom)),
+            &triggers.high.to_string(),
+        )?;
+        self.write(&format!("{}/templow", path(rom)), &triggers.low.to_string())
+    }
+
+    fn request(
+        &self,
+        message: i32,
+        path: &str,
+        data: &[u8],
+        size: i32,
+    ) -> Result<TcpStream, Error<io::Error>> {
+        let mut stream = TcpStream::connect(self.address).map_err(Error::Bus)?;
+        let payload = path.len() + 1 + data.len();
+        let mut bytes = Vec::with_capacity(24 + payload);
+        for value in [0, payload as i32, message, FLAGS, size, 0] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(data);
+        stream.write_all(&bytes).map_err(Error::Bus)?;
+        Ok(stream)
+    }
+}
+
+/// Reads a response, skipping the keep-alive ones.
+///
+/// Returns the payload truncated to the size, or `None` if the server closed
+/// the connection. A payload much longer than the requested size is rejected
+/// before it is read.
+fn response(stream: &mut TcpStream, requested: i32) -> Result<Option<Vec<u8>>, Error<io::Error>> {
+    loop {
+        let mut header = [0; 24];
+        match stream.read_exact(&mut header) {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result.map_err(Error::Bus)?,
+        }
+        let [_, payload, ret, _, size, _] = core::array::from_fn(|index| {
+            i32::from_be_bytes(header[4 * index..4 * index + 4].try_into().unwrap())
+        });
+        if payload == PING {
+            continue;
+        }
+        if ret < 0 {
+            Err(Ds18b20Error::ServerError { ret })?;
+        }
+        if payload > requested + MARGIN {
+            Err(Ds18b20Error::UnexpectedResponse)?;
+        }
+        let mut bytes = std::vec![0; payload.max(0) as usize];
+        stream.read_exact(&mut bytes).map_err(Error::Bus)?;
+        bytes.truncate(size.max(0) as usize);
+        return Ok(Some(bytes));
+    }
+}
+
+/// Returns the path of the device, e.g. `/28.0316A2791DFF`.
+///
+/// The serial number is written in the order it is sent on the bus.
+fn path(rom: &Rom) -> String {
+    let mut path = format!("/{:02X}.", rom.family_code);
+    for byte in rom.serial_number {
+        path += &format!("{byte:02X}");
+    }
+    path
+}
+
+/// Parses the device path, the CRC is not part of it and is calculated.
+fn parse_rom(path: &str) -> Option<Rom> {
+    let name = path.trim_start_matches('/');
+    let (family_code, serial_number) = name.split_once('.')?;
+    if family_code.len() != 2 || serial_number.len() != 12 {
+        return None;
+    }
+    let mut bytes = [0; 8];
+    bytes[0] = u8::from_str_radix(family_code, 16).ok()?;
+    for (index, byte) in bytes[1..7].iter_mut().enumerate() {
+        *byte = u8::from_str_radix(serial_number.get(2 * index..2 * index + 2)?, 16).ok()?;
+    }
+    bytes[7] = calculate(&bytes[..7]);
+    bytes.try_into().ok()
+}
+
+fn to_string(payload: &[u8]) -> Result<String, Error<io::Error>> {
+    let value =
+        String::from_utf8(payload.to_vec()).map_err(|_| Ds18b20Error::UnexpectedResponse)?;
+    Ok(value.trim_end_matches('\0').to_string())
+}
+
+fn parse<T: core::str::FromStr>(value: &str) -> Result<T, Error<io::Error>> {
+    Ok(value
+        .trim()
+        .parse()
+        .map_err(|_| Ds18b20Error::UnexpectedResponse)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{net::TcpListener, thread, vec};
+
+    /// Serves the requests with the responses of the mock, recording the
+    /// requests.
+    fn serve(listener: TcpListener, requests: usize) -> thread::JoinHandle<Vec<(i32, String)>> {
+        thread::spawn(move || {
+            let mut received = Vec::new();
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut header = [0; 24];
+                stream.read_exact(&mut header).unwrap();
+                let payload = i32::from_be_bytes(header[4..8].try_into().unwrap());
+                let message = i32::from_be_bytes(header[8..12].try_into().unwrap());
+                let mut bytes = vec![0; payload as usize];
+                stream.read_exact(&mut bytes).unwrap();
+                let end = bytes.iter().position(|&byte| byte == 0).unwrap();
+                let path = core::str::from_utf8(&bytes[..end]).unwrap().to_string();
+                // A keep-alive response first.
+                let mut response = Vec::new();
+                for value in [0, PING, 0, 0, 0, 0] {
+                    response.extend_from_slice(&value.to_be_bytes());
+                }
+                for (ret, data) in mock(message, &path, &bytes[end + 1..]) {
+                    let len = data.len() as i32;
+                    for value in [0, len, ret, 0, len, 0] {
+                        response.extend_from_slice(&value.to_be_bytes());
+                    }
+                    response.extend_from_slice(&data);
+                }
+                stream.write_all(&response).unwrap();
+                received.push((message, path));
+            }
+            received
+        })
+    }
+
+    fn mock(message: i32, path: &str, data: &[u8]) -> Vec<(i32, Vec<u8>)> {
+        let value = |value: &str| vec![(0, value.as_bytes().to_vec())];
+        match (message, path) {
+            (MESSAGE_DIR, "/") => vec![
+                (0, b"/28.FF1D79A21603\0".to_vec()),
+                (0, b"/10.A1F8D4020800\0".to_vec()),
+                (0, b"/bus.0\0".to_vec()),
+                (0, vec![]),
+            ],
+            (MESSAGE_READ, "/28.FF1D79A21603/temperature12") => value("     23.125"),
+            (MESSAGE_READ, "/28.FF1D79A21603/power") => value("           0"),
+            (MESSAGE_READ, "/28.FF1D79A21603/temphigh") => value("          75"),
+            (MESSAGE_READ, "/28.FF1D79A21603/templow") => value("         -10"),
+            (MESSAGE_WRITE, "/28.FF1D79A21603/temphigh") if data == b"30" => vec![(0, vec![])],
+            (MESSAGE_PRESENCE, "/28.FF1D79A21603") => vec![(0, vec![])],
+            // EIO
+            (MESSAGE_PRESENCE, "/28.FFFFFFFFFFFF") => vec![(-5, vec![])],
+            (MESSAGE_READ, "/28.FF1D79A21603/scratchpad") => {
+                vec![(0, vec![0; (SIZE + MARGIN + 1) as usize])]
+            }
+            _ => vec![(ENOENT, vec![])],
+        }
+    }
+
+    #[test]
+    fn requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let owserver = Owserver::new(listener.local_addr().unwrap());
+        let server = serve(listener, 10);
+
+        let roms = owserver.devices().unwrap();
+        assert_eq!(1, roms.len());
+        assert_eq!([0xFF, 0x1D, 0x79, 0xA2, 0x16, 0x03], roms[0].serial_number);
+        let temperature = owserver.read_temperature(&roms[0], Resolution::Twelve);
+        assert_eq!(23_125, temperature.unwrap().to_millidegrees_celsius());
+        assert_eq!(
+            PowerSupply::Parasite,
+            owserver.read_power_supply(&roms[0]).unwrap()
+        );
+        let triggers = owserver.triggers(&roms[0]).unwrap();
+        assert_eq!((75, -10), (triggers.high, triggers.low));
+        owserver.write("/28.FF1D79A21603/temphigh", "30").unwrap();
+        assert!(owserver.presence("/28.FF1D79A21603").unwrap());
+        assert!(!owserver.presence("/28.000000000000").unwrap());
+        assert_eq!(
+            Err(Ds18b20Error::ServerError { ret: -5 }),
+            owserver
+                .presence("/28.FFFFFFFFFFFF")
+                .map_err(|error| match error {
+                    Error::Ds18b20(error) => error,
+                    error => panic!("{error:?}"),
+                })
+        );
+        assert!(matches!(
+            owserver.read("/28.FF1D79A21603/scratchpad"),
+            Err(Error::Ds18b20(Ds18b20Error::UnexpectedResponse))
+        ));
+        let received = server.join().unwrap();
+        assert_eq!((MESSAGE_DIR, "/".to_string()), received[0]);
+        assert_eq!(MESSAGE_WRITE, received[5].0);
+    }
+}
@@ -0,0 +1,87 @@
+use embedded_hal::digital::OutputPin;
+
+/// Strong pull-up
+///
+/// Supplies parasite-powered devices through a low impedance path during
+/// temperature conversions and EEPROM writes: a MOSFET switched by a separate
+/// pin, or the bus pin switched to push-pull. `E` is the error of the bus pin.
+pub trait StrongPullUp<E> {
+    fn enable(&mut self) -> Result<(), E>;
+
+    fn disable(&mut self) -> Result<(), E>;
+}
+
+/// No strong pull-up, the pull-up resistor only.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoStrongPullUp;
+
+impl<E> StrongPullUp<E> for NoStrongPullUp {
+    fn enable(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Strong pull-up enabled by setting the pin high, e.g. driving a gate driver.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ActiveHigh<T>(pub T);
+
+impl<T: OutputPin> StrongPullUp<T::Error> for ActiveHigh<T> {
+    fn enable(&mut self) -> Result<(), T::Error> {
+        self.0.set_high()
+    }
+
+    fn disable(&mut self) -> Result<(), T::Error> {
+        self.0.set_low()
+    }
+}
+
+/// Strong pull-up enabled by setting the pin low, e.g. the gate of a P-channel
+/// MOSFET between the supply and the bus.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ActiveLow<T>(pub T);
+
+impl<T: OutputPin> StrongPullUp<T::Error> for ActiveLow<T> {
+    fn enable(&mut self) -> Result<(), T::Error> {
+        self.0.set_low()
+    }
+
+    fn disable(&mut self) -> Result<(), T::Error> {
+        self.0.set_high()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use crate::{ActiveLow, Driver, OneWireBus};
+    use embedded_hal_mock::eh1::{
+        delay::NoopDelay,
+        digital::{Mock, State, Transaction},
+    };
+
+    #[test]
+    fn strong_pull_up() {
+        // Convert T, written twice: the pin is released after each bit.
+        let transactions: std::vec::Vec<_> = core::iter::once(Transaction::set(State::High))
+            .chain(
+                (0..16).flat_map(|_| [Transaction::set(State::Low), Transaction::set(State::High)]),
+            )
+            .collect();
+        let mut pin = Mock::new(&transactions);
+        let mut pull_up = Mock::new(&[Transaction::set(State::Low), Transaction::set(State::High)]);
+        let mut driver = Driver::new(pin.clone(), NoopDelay)
+            .unwrap()
+            .with_strong_pull_up(ActiveLow(pull_up.clone()));
+        // Externally powered devices only.
+        driver.write_command_with_pull_up(0x44, 10_000_000).unwrap();
+        driver.set_parasite_power(true);
+        driver.write_command_with_pull_up(0x44, 10_000_000).unwrap();
+        pin.done();
+        pull_up.done();
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    commands::memory::convert_devices, Ds18b20, Ds18b20Error, Error, MemoryCommands, OneWireBus,
+    Rom, RomCommands, Scratchpad, SearchState, Temperatures,
+};
+
+/// Retry policy
+///
+/// Repeats a whole transaction (initialization, ROM command and memory
+/// function command) when it fails with a retryable error, e.g. a single noisy
+/// slot corrupting the CRC. The operations return the number of attempts made
+/// along with the result, see [`Retried`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one (at least one
+    /// attempt is made).
+    pub attempts: u8,
+    /// Time (ns) to wait before each retry.
+    pub backoff: u32,
+    /// Errors worth another attempt.
+    pub retryable: Retryable,
+}
+
+impl RetryPolicy {
+    /// A single attempt, no retries.
+    pub const fn none() -> Self {
+        Self {
+            attempts: 1,
+            backoff: 0,
+            retryable: Retryable::NONE,
+        }
+    }
+
+    /// Runs the operation until it succeeds, fails with an error which isn't
+    /// retryable or the attempts are exhausted.
+    pub fn retry<B: OneWireBus, T>(
+        &self,
+        bus: &mut B,
+        mut operation: impl FnMut(&mut B) -> Result<T, Error<B::Error>>,
+    ) -> Result<Retried<T>, Retried<Error<B::Error>>> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match operation(bus) {
+                Ok(value) => return Ok(Retried { value, attempts }),
+                Err(error) if attempts < self.attempts && self.retryable.contains(&error) => {
+                    bus.report_retry(&error);
+                    bus.delay(self.backoff);
+                }
+                Err(value) => return Err(Retried { value, attempts }),
+            }
+        }
+    }
+
+    /// Reads the scratchpad of the device.
+    pub fn read_scratchpad<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+        device: &Ds18b20,
+    ) -> Result<Retried<Scratchpad>, Retried<Error<B::Error>>> {
+        self.retry(bus, |bus| device.read_scratchpad(bus))
+    }
+
+    /// Reads the ROM of the single device on the bus.
+    pub fn read_rom<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Retried<Rom>, Retried<Error<B::Error>>> {
+        self.retry(bus, |bus| {
+            if !bus.initialization()? {
+                return Err(bus.report(Ds18b20Error::NoAttachedDevices.into()));
+            }
+            bus.read_rom()
+        })
+    }
+
+    /// Searches the next device, see [`RomCommands::search_rom`].
+    ///
+    /// The state only advances on success, so a ROM failing the CRC check is
+    /// searched again.
+    pub fn search_rom<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+        state: &mut SearchState,
+    ) -> Result<Retried<Option<Rom>>, Retried<Error<B::Error>>> {
+        let retried = self.retry(bus, |bus| {
+            let mut next = *state;
+            let rom = bus.search_rom(&mut next)?;
+            Ok((rom, next))
+        })?;
+        *state = retried.value.1;
+        Ok(Retried {
+            value: retried.value.0,
+            attempts: retried.attempts,
+        })
+    }
+
+    /// Begins a temperature conversion on the device, see
+    /// [`Ds18b20::start_conversion`].
+    pub fn start_conversion<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+        device: &Ds18b20,
+    ) -> Result<Retried<()>, Retried<Error<B::Error>>> {
+        self.retry(bus, |bus| device.start_conversion(bus))
+    }
+
+    /// Begins a temperature conversion on all devices, see
+    /// [`MemoryCommands::start_conversion_all`].
+    pub fn start_conversion_all<B: OneWireBus>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Retried<()>, Retried<Error<B::Error>>> {
+        self.retry(bus, |bus| bus.start_conversion_all())
+    }
+
+    /// Converts the temperature on all devices simultaneously, see
+    /// [`MemoryCommands::convert_all`].
+    ///
+    /// The conversion is retried, then each read of the returned iterator.
+    pub fn convert_all<'a, B: OneWireBus>(
+        &self,
+        bus: &'a mut B,
+        devices: &'a [Ds18b20],
+    ) -> Result<Retried<Temperatures<'a, B>>, Retried<Error<B::Error>>> {
+        let retried = self.retry(bus, |bus| convert_devices(bus, devices))?;
+        Ok(Retried {
+            value: Temperatures::new(bus, devices, *self),
+            attempts: retried.attempts,
+        })
+    }
+}
+
+/// Three attempts, 1 ms apart, on CRC errors, missing presence pulses and
+/// unexpected responses.
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: 1_000_000,
+            retryable: Retryable::ALL,
+        }
+    }
+}
+
+/// Retryable errors
+///
+/// The errors a noisy line may cause. Pin and bus errors of the master and
+/// the data errors (e.g. the power-on reset value) are never retried.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Retryable {
+    /// [`Ds18b20Error::UnexpectedCrc`]
+    pub crc: bool,
+    /// [`Ds18b20Error::NoAttachedDevices`]
+    pub presence: bool,
+    /// [`Ds18b20Error::UnexpectedResponse`] and [`Ds18b20Error::NoResponse`]
+    pub response: bool,
+}
+
+impl Retryable {
+    pub const NONE: Self = Self {
+        crc: false,
+        presence: false,
+        response: false,
+    };
+
+    pub const ALL: Self = Self {
+        crc: true,
+        presence: true,
+        response: true,
+    };
+
+    /// Returns `true` if the error is retryable.
+    pub fn contains<E>(&self, error: &Error<E>) -> bool {
+        match error {
+            Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. }) => self.crc,
+            Error::Ds18b20(Ds18b20Error::NoAttachedDevices) => self.presence,
+            Error::Ds18b20(Ds18b20Error::UnexpectedResponse | Ds18b20Error::NoResponse) => {
+                self.response
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Retried
+///
+/// The result of an operation (the value or the last error) and the number
+/// of attempts it took.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Retried<T> {
+    pub value: T,
+    pub attempts: u8,
+}
+
+impl<T> From<Retried<Error<T>>> for Error<T> {
+    fn from(retried: Retried<Error<T>>) -> Self {
+        retried.value
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::{
+        simulator::{
+            testing::{bus, device, noisy},
+            Delay, Faults, Pin,
+        },
+        Driver, Statistics, Temperature,
+    };
+    use std::vec::Vec;
+
+    #[test]
+    fn read_scratchpad() {
+        let device = device(0x6F);
+        let ds18b20 = Ds18b20::new(*device.rom()).unwrap();
+        let bus = bus(&[device], noisy());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus))
+            .unwrap()
+            .with_observer(Statistics::<1>::new());
+        let policy = RetryPolicy {
+            attempts: 10,
+            ..Default::default()
+        };
+        let mut retries = 0;
+        for _ in 0..100 {
+            let retried = policy.read_scratchpad(&mut driver, &ds18b20).unwrap();
+            assert_eq!(device.scratchpad(), retried.value.to_bytes());
+            retries += u32::from(retried.attempts) - 1;
+        }
+        assert_ne!(0, retries);
+        let counters = driver.observer().device(device.rom()).unwrap();
+        assert_eq!(retries, counters.retries);
+        // Without retries the CRC errors surface.
+        let errors = (0..100)
+            .filter_map(|_| {
+                RetryPolicy::none()
+                    .read_scratchpad(&mut driver, &ds18b20)
+                    .err()
+            })
+            .inspect(|retried| assert_eq!(1, retried.attempts))
+            .count();
+        assert_ne!(0, errors);
+    }
+
+    #[test]
+    fn exhausted() {
+        let device = device(0x6F);
+        let bus = bus(
+            &[device],
+            Faults {
+                missing_presence: true,
+                ..Default::default()
+            },
+        );
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let policy = RetryPolicy::default();
+        let start = bus.borrow().now();
+        assert_eq!(
+            Err(Retried {
+                value: Error::Ds18b20(Ds18b20Error::NoAttachedDevices),
+                attempts: 3,
+            }),
+            policy.read_rom(&mut driver)
+        );
+        assert!(bus.borrow().now() - start >= 2 * policy.backoff as u64);
+        // Not retryable.
+        let policy = RetryPolicy {
+            retryable: Retryable {
+                presence: false,
+                ..Retryable::ALL
+            },
+            ..policy
+        };
+        assert_eq!(
+            1,
+            policy
+                .start_conversion_all(&mut driver)
+                .unwrap_err()
+                .attempts
+        );
+    }
+
+    #[test]
+    fn search_rom() {
+        let devices = [device(0x6F), device(0x01), device(0x2A)];
+        let bus = bus(&devices, noisy());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let policy = RetryPolicy {
+            attempts: 10,
+            ..Default::default()
+        };
+        let mut state = SearchState::default();
+        let mut roms = Vec::new();
+        while let Some(rom) = policy.search_rom(&mut driver, &mut state).unwrap().value {
+            roms.push(rom);
+        }
+        let mut expected: Vec<_> = devices.iter().map(|device| *device.rom()).collect();
+        expected.sort_by_key(|&rom| u64::from(rom).reverse_bits());
+        assert_eq!(expected, roms);
+    }
+
+    #[test]
+    fn convert_all() {
+        let devices = [(0x6F, 21), (0x01, -5), (0x2A, 30)].map(|(serial_number, celsius)| {
+            device(serial_number).with_temperature(Temperature::from_celsius(celsius))
+        });
+        let ds18b20s = devices.map(|device| Ds18b20::new(*device.rom()).unwrap());
+        let bus = bus(&devices, noisy());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let policy = RetryPolicy {
+            attempts: 10,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let temperatures: Vec<_> = policy
+                .convert_all(&mut driver, &ds18b20s)
+                .unwrap()
+                .value
+                .map(|(_, temperature)| temperature.unwrap().to_millidegrees_celsius() / 1_000)
+                .collect();
+            assert_eq!(std::vec![21, -5, 30], temperatures);
+        }
+    }
+}
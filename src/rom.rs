@@ -13,6 +13,10 @@ impl TryFrom<[u8; 8]> for Rom {
     type Error = Ds18b20Error;
 
     fn try_from(value: [u8; 8]) -> Result<Self, Self::Error> {
+        // The CRC of all zeros is 0 as well.
+        if value == [0x00; 8] {
+            return Err(Ds18b20Error::AllZeros);
+        }
         check(&value)?;
         Ok(Self {
             family_code: value[0],
@@ -52,6 +56,7 @@ impl From<Rom> for u64 {
 }
 
 #[test]
+#[allow(clippy::unusual_byte_groupings)]
 fn test() {
     assert_eq!(
         Ok(Rom {
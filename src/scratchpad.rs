@@ -1,40 +1,115 @@
-use crate::{crc8::check, error::Ds18b20Error};
+use crate::{crc8::check, error::Ds18b20Error, Temperature};
 
 pub(crate) const NINE: u8 = 0b00011111;
 pub(crate) const TEN: u8 = 0b00111111;
 pub(crate) const ELEVEN: u8 = 0b01011111;
 pub(crate) const TWELVE: u8 = 0b01111111;
 
+/// Temperature register (LSB, MSB) and byte 6 on power-up.
+const POWER_ON_RESET_REGISTER: [u8; 2] = [0x50, 0x05];
+const POWER_ON_RESET_COUNT_REMAIN: u8 = 0x0C;
+
 /// Max conversion time, up to 750 ms.
 const CONVERSION_TIME_NS: u32 = 750_000_000;
 
 /// Scratchpad
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Scratchpad {
-    pub temperature: f32,
+    /// Temperature register as read (LSB, MSB), including the bits below the
+    /// resolution.
+    pub temperature_register: [u8; 2],
     pub configuration_register: ConfigurationRegister,
     pub triggers: Triggers,
+    /// Reserved bytes 5, 6 and 7.
+    ///
+    /// Genuine DS18B20s read `0xFF`, "COUNT REMAIN" and `0x10`. The DS18S20
+    /// and some clones use byte 6 as "COUNT REMAIN".
+    pub reserved: [u8; 3],
     pub crc: u8,
 }
 
+impl Scratchpad {
+    /// Returns the bytes as read from the device.
+    ///
+    /// The temperature comes from the raw temperature register.
+    pub fn to_bytes(&self) -> [u8; 9] {
+        [
+            self.temperature_register[0],
+            self.temperature_register[1],
+            self.triggers.high as _,
+            self.triggers.low as _,
+            self.configuration_register.into(),
+            self.reserved[0],
+            self.reserved[1],
+            self.reserved[2],
+            self.crc,
+        ]
+    }
+
+    /// Returns the temperature of the temperature register, at the resolution
+    /// of the configuration register.
+    pub fn temperature(&self) -> Temperature {
+        let [lsb, msb] = self.temperature_register;
+        to_temperature(msb, lsb, self.configuration_register.resolution)
+    }
+
+    /// Returns the reserved byte 6, "COUNT REMAIN".
+    pub fn count_remain(&self) -> u8 {
+        self.reserved[1]
+    }
+
+    /// Returns `true` if the temperature register holds the power-on reset
+    /// value, i.e. the device has lost power or has never converted the
+    /// temperature since power-up.
+    ///
+    /// A measured +85 °C reads the same temperature, but a conversion also
+    /// updates byte 6, which is `0x0C` on power-up.
+    pub fn is_power_on_reset(&self) -> bool {
+        self.temperature_register == POWER_ON_RESET_REGISTER
+            && self.count_remain() == POWER_ON_RESET_COUNT_REMAIN
+    }
+
+    /// Returns the temperature measured by the last conversion.
+    ///
+    /// The power-on reset value and the values out of the measurement range
+    /// are reported as errors, see [`Temperature::check`].
+    pub fn measured_temperature(&self) -> Result<Temperature, Ds18b20Error> {
+        if self.is_power_on_reset() {
+            return Err(Ds18b20Error::PowerOnReset);
+        }
+        self.temperature().check()
+    }
+}
+
 impl TryFrom<[u8; 9]> for Scratchpad {
     type Error = Ds18b20Error;
 
     fn try_from(value: [u8; 9]) -> Result<Self, Self::Error> {
+        // The bus is left floating high if no device drives it.
+        if value == [0xFF; 9] {
+            return Err(Ds18b20Error::NoResponse);
+        }
         check(&value)?;
         let configuration_register = ConfigurationRegister::try_from(value[4])?;
         Ok(Scratchpad {
-            temperature: to_temperature(value[1], value[0], configuration_register.resolution),
+            temperature_register: [value[0], value[1]],
             triggers: Triggers {
                 high: value[2] as _,
                 low: value[3] as _,
             },
             configuration_register,
+            reserved: [value[5], value[6], value[7]],
             crc: value[8],
         })
     }
 }
 
+impl From<Scratchpad> for [u8; 9] {
+    fn from(value: Scratchpad) -> Self {
+        value.to_bytes()
+    }
+}
+
 /// Configuration register
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct ConfigurationRegister {
@@ -105,19 +180,15 @@ pub struct Triggers {
     pub low: i8,
 }
 
-pub fn to_temperature(msb: u8, lsb: u8, resolution: Resolution) -> f32 {
-    let divider = match resolution {
-        Resolution::Nine => 2.0,
-        Resolution::Ten => 4.0,
-        Resolution::Eleven => 8.0,
-        Resolution::Twelve => 16.0,
-    };
-    i16::from_be_bytes([msb, lsb]) as f32 / divider
+pub fn to_temperature(msb: u8, lsb: u8, resolution: Resolution) -> Temperature {
+    Temperature::from_register(msb, lsb, resolution)
 }
 
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
 mod test {
     use super::*;
+    use crate::crc8::calculate;
 
     #[test]
     fn configuration_register() {
@@ -159,18 +230,88 @@ mod test {
         );
     }
 
+    #[test]
+    fn bytes() {
+        let mut bytes = [0x97, 0x01, 0x4B, 0x46, 0x1F, 0xFF, 0x0F, 0x10, 0x00];
+        bytes[8] = calculate(&bytes[..8]);
+        let scratchpad = Scratchpad::try_from(bytes).unwrap();
+        assert_eq!(Temperature::from_raw(0x0190), scratchpad.temperature());
+        assert_eq!([0xFF, 0x0F, 0x10], scratchpad.reserved);
+        assert_eq!(0x0F, scratchpad.count_remain());
+        assert_eq!(bytes, scratchpad.to_bytes());
+    }
+
+    #[test]
+    fn power_on_reset() {
+        let mut bytes = [0x50, 0x05, 0x4B, 0x46, 0x7F, 0xFF, 0x0C, 0x10, 0x00];
+        bytes[8] = calculate(&bytes[..8]);
+        let scratchpad = Scratchpad::try_from(bytes).unwrap();
+        assert!(scratchpad.is_power_on_reset());
+        assert_eq!(
+            Err(Ds18b20Error::PowerOnReset),
+            scratchpad.measured_temperature()
+        );
+        // A measured +85 °C.
+        bytes[6] = 0x10;
+        bytes[8] = calculate(&bytes[..8]);
+        let scratchpad = Scratchpad::try_from(bytes).unwrap();
+        assert!(!scratchpad.is_power_on_reset());
+        assert_eq!(
+            Ok(Temperature::from_celsius(85)),
+            scratchpad.measured_temperature()
+        );
+    }
+
+    #[test]
+    fn no_response() {
+        assert_eq!(
+            Err(Ds18b20Error::NoResponse),
+            Scratchpad::try_from([0xFF; 9]).map(|scratchpad| scratchpad.crc),
+        );
+    }
+
     #[test]
     fn temperature() {
         // Temperature
-        assert_eq!(125.0, to_temperature(0x07, 0xD0, Default::default()));
-        assert_eq!(85.0, to_temperature(0x05, 0x50, Default::default()));
-        assert_eq!(25.0625, to_temperature(0x01, 0x91, Default::default()));
-        assert_eq!(10.125, to_temperature(0x00, 0xA2, Default::default()));
-        assert_eq!(0.5, to_temperature(0x00, 0x08, Default::default()));
-        assert_eq!(0.0, to_temperature(0x00, 0x00, Default::default()));
-        assert_eq!(-0.5, to_temperature(0xFF, 0xF8, Default::default()));
-        assert_eq!(-10.125, to_temperature(0xFF, 0x5E, Default::default()));
-        assert_eq!(-25.0625, to_temperature(0xFE, 0x6F, Default::default()));
-        assert_eq!(-55.0, to_temperature(0xFC, 0x90, Default::default()));
+        assert_eq!(
+            125.0,
+            f32::from(to_temperature(0x07, 0xD0, Default::default()))
+        );
+        assert_eq!(
+            85.0,
+            f32::from(to_temperature(0x05, 0x50, Default::default()))
+        );
+        assert_eq!(
+            25.0625,
+            f32::from(to_temperature(0x01, 0x91, Default::default()))
+        );
+        assert_eq!(
+            10.125,
+            f32::from(to_temperature(0x00, 0xA2, Default::default()))
+        );
+        assert_eq!(
+            0.5,
+            f32::from(to_temperature(0x00, 0x08, Default::default()))
+        );
+        assert_eq!(
+            0.0,
+            f32::from(to_temperature(0x00, 0x00, Default::default()))
+        );
+        assert_eq!(
+            -0.5,
+            f32::from(to_temperature(0xFF, 0xF8, Default::default()))
+        );
+        assert_eq!(
+            -10.125,
+            f32::from(to_temperature(0xFF, 0x5E, Default::default()))
+        );
+        assert_eq!(
+            -25.0625,
+            f32::from(to_temperature(0xFE, 0x6F, Default::default()))
+        );
+        assert_eq!(
+            -55.0,
+            f32::from(to_temperature(0xFC, 0x90, Default::default()))
+        );
     }
 }
@@ -0,0 +1,358 @@
+use crate::{
+    commands::{
+        memory::{
+            COMMAND_MEMORY_CONVERT, COMMAND_MEMORY_POWER_SUPPLY_READ, COMMAND_MEMORY_RECALL,
+            COMMAND_MEMORY_SCRATCHPAD_COPY, COMMAND_MEMORY_SCRATCHPAD_READ,
+            COMMAND_MEMORY_SCRATCHPAD_WRITE,
+        },
+        rom::{
+            COMMAND_ALARM_SEARCH, COMMAND_ROM_MATCH, COMMAND_ROM_READ, COMMAND_ROM_SEARCH,
+            COMMAND_ROM_SKIP,
+        },
+    },
+    crc8::calculate,
+    scratchpad::{ConfigurationRegister, Resolution},
+    Rom, Temperature, FAMILY_CODE,
+};
+
+/// EEPROM write time of the simulated devices.
+const COPY_SCRATCHPAD_TIME_NS: u64 = 2_000_000;
+
+/// Power-on scratchpad (without the CRC): 85 °C, TH 75 °C, TL 70 °C, 12 bits.
+const POWER_ON_SCRATCHPAD: [u8; 8] = [0x50, 0x05, 0x4B, 0x46, 0x7F, 0xFF, 0x0C, 0x10];
+
+/// Simulated DS18B20
+///
+/// A state machine following the slots of the bus: it receives the ROM and
+/// function commands with their data, and transmits the bits of its ROM,
+/// scratchpad, power supply and busy flag.
+#[derive(Clone, Copy, Debug)]
+pub struct Device {
+    rom: Rom,
+    temperature: Temperature,
+    parasite_power: bool,
+    scratchpad: [u8; 8],
+    eeprom: [u8; 3],
+    alarm: bool,
+    /// End of the temperature conversion in progress.
+    conversion: Option<u64>,
+    quirk: Option<Quirk>,
+    state: State,
+}
+
+/// Clone quirk
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quirk {
+    /// Stores the unused configuration register bits.
+    UnusedBits,
+    /// Reads the reserved bytes 5 through 7 as the given ones.
+    Reserved([u8; 3]),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    /// Waits for a reset.
+    Idle,
+    Receive {
+        command: Receive,
+        value: u64,
+        bits: u32,
+    },
+    Transmit {
+        bytes: [u8; 9],
+        bits: u32,
+        index: u32,
+        /// A function command follows.
+        function: bool,
+    },
+    /// Search: sends the bit, sends its complement, receives the direction.
+    Search { index: u32, step: u8 },
+    /// Reads 0 until the end of the operation, then 1.
+    Busy { until: u64 },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Receive {
+    RomCommand,
+    MatchRom,
+    FunctionCommand,
+    WriteScratchpad,
+}
+
+impl Receive {
+    fn bits(&self) -> u32 {
+        match self {
+            Receive::RomCommand | Receive::FunctionCommand => 8,
+            Receive::MatchRom => 64,
+            Receive::WriteScratchpad => 24,
+        }
+    }
+}
+
+impl Device {
+    /// Powered-on device, externally powered, at 25 °C.
+    pub fn new(rom: Rom) -> Self {
+        Self {
+            rom,
+            temperature: Temperature::from_celsius(25),
+            parasite_power: false,
+            scratchpad: POWER_ON_SCRATCHPAD,
+            eeprom: [0x4B, 0x46, 0x7F],
+            alarm: false,
+            conversion: None,
+            quirk: None,
+            state: State::Idle,
+        }
+    }
+
+    /// Device with a DS18B20 ROM, the CRC is calculated.
+    pub fn with_serial_number(serial_number: [u8; 6]) -> Self {
+        let mut bytes = [0; 8];
+        bytes[0] = FAMILY_CODE;
+        bytes[1..7].copy_from_slice(&serial_number);
+        bytes[7] = calculate(&bytes[..7]);
+        Self::new(bytes.try_into().unwrap())
+    }
+
+    /// Sets the temperature measured by the following conversions.
+    pub fn with_temperature(mut self, temperature: Temperature) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Clone with the quirk.
+    pub fn with_quirk(mut self, quirk: Quirk) -> Self {
+        self.quirk = Some(quirk);
+        if let Quirk::Reserved(reserved) = quirk {
+            self.scratchpad[5..].copy_from_slice(&reserved);
+        }
+        self
+    }
+
+    /// Parasite-powered device.
+    pub fn with_parasite_power(mut self) -> Self {
+        self.parasite_power = true;
+        self
+    }
+
+    pub fn rom(&self) -> &Rom {
+        &self.rom
+    }
+
+    pub fn temperature(&self) -> Temperature {
+        self.temperature
+    }
+
+    pub fn set_temperature(&mut self, temperature: Temperature) {
+        self.temperature = temperature;
+    }
+
+    /// Removes and restores the power: the scratchpad is reset to its
+    /// power-on value (85 °C) and the EEPROM recalled.
+    pub fn power_cycle(&mut self) {
+        self.scratchpad[..2].copy_from_slice(&POWER_ON_SCRATCHPAD[..2]);
+        self.scratchpad[2..5].copy_from_slice(&self.eeprom);
+        if !matches!(self.quirk, Some(Quirk::Reserved(_))) {
+            self.scratchpad[5..].copy_from_slice(&POWER_ON_SCRATCHPAD[5..]);
+        }
+        self.alarm = false;
+        self.conversion = None;
+        self.state = State::Idle;
+    }
+
+    /// Returns the scratchpad, including the CRC.
+    pub fn scratchpad(&self) -> [u8; 9] {
+        let mut bytes = [0; 9];
+        bytes[..8].copy_from_slice(&self.scratchpad);
+        bytes[8] = calculate(&self.scratchpad);
+        bytes
+    }
+
+    /// Returns the TH, TL and configuration register bytes saved to EEPROM.
+    pub fn eeprom(&self) -> [u8; 3] {
+        self.eeprom
+    }
+
+    /// Returns `true` if the last conversion set the alarm flag.
+    pub fn alarm(&self) -> bool {
+        self.alarm
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        ConfigurationRegister::try_from(self.scratchpad[4])
+            .map(|configuration_register| configuration_register.resolution)
+            .unwrap_or_default()
+    }
+
+    /// Reset pulse, returns `true` if the device sends a presence pulse.
+    pub(super) fn reset(&mut self, now: u64) -> bool {
+        self.update(now);
+        self.state = receive(Receive::RomCommand);
+        true
+    }
+
+    /// Start of a slot, returns `true` if the device pulls the bus low to
+    /// send a 0.
+    pub(super) fn slot(&mut self, now: u64) -> bool {
+        self.update(now);
+        match self.state {
+            State::Transmit { bytes, index, .. } => !bit(&bytes, index),
+            State::Search { index, step: 0 } => !self.rom_bit(index),
+            State::Search { index, step: 1 } => self.rom_bit(index),
+            State::Busy { until } => now < until,
+            _ => false,
+        }
+    }
+
+    /// End of a slot: the bit written by the master.
+    pub(super) fn written(&mut self, bit: bool, now: u64) {
+        match &mut self.state {
+            State::Receive {
+                command,
+                value,
+                bits,
+            } => {
+                *value |= (bit as u64) << *bits;
+                *bits += 1;
+                if *bits == command.bits() {
+                    let (command, value) = (*command, *value);
+                    self.received(command, value, now);
+                }
+            }
+            State::Transmit {
+                bits,
+                index,
+                function,
+                ..
+            } => {
+                *index += 1;
+                if index == bits {
+                    self.state = if *function {
+                        receive(Receive::FunctionCommand)
+                    } else {
+                        State::Idle
+                    };
+                }
+            }
+            State::Search { step, .. } if *step < 2 => *step += 1,
+            State::Search { index, .. } => {
+                let index = *index;
+                self.state = if bit != self.rom_bit(index) {
+                    State::Idle
+                } else if index == u64::BITS - 1 {
+                    receive(Receive::FunctionCommand)
+                } else {
+                    State::Search {
+                        index: index + 1,
+                        step: 0,
+                    }
+                };
+            }
+            State::Idle | State::Busy { .. } => {}
+        }
+    }
+
+    /// Completes the conversion in progress.
+    pub(super) fn update(&mut self, now: u64) {
+        if self.conversion.is_some_and(|end| end <= now) {
+            self.conversion = None;
+            // The bits below the resolution are undefined, read as 0.
+            let undefined = match self.resolution() {
+                Resolution::Nine => 0b111,
+                Resolution::Ten => 0b11,
+                Resolution::Eleven => 0b1,
+                Resolution::Twelve => 0b0,
+            };
+            let raw = self.temperature.raw() & !undefined;
+            self.scratchpad[..2].copy_from_slice(&raw.to_le_bytes());
+            if !matches!(self.quirk, Some(Quirk::Reserved(_))) {
+                self.scratchpad[6] = 0x10 - (raw as u8 & 0x0F);
+            }
+            let integer = raw >> 4;
+            self.alarm = integer >= self.scratchpad[2] as i8 as i16
+                || integer <= self.scratchpad[3] as i8 as i16;
+        }
+    }
+
+    fn received(&mut self, command: Receive, value: u64, now: u64) {
+        self.state = match (command, value as u8) {
+            (Receive::RomCommand, COMMAND_ROM_READ) => transmit(&<[u8; 8]>::from(self.rom), true),
+            (Receive::RomCommand, COMMAND_ROM_MATCH) => receive(Receive::MatchRom),
+            (Receive::RomCommand, COMMAND_ROM_SKIP) => receive(Receive::FunctionCommand),
+            (Receive::RomCommand, COMMAND_ROM_SEARCH) => State::Search { index: 0, step: 0 },
+            (Receive::RomCommand, COMMAND_ALARM_SEARCH) if self.alarm => {
+                State::Search { index: 0, step: 0 }
+            }
+            (Receive::MatchRom, _) if value == u64::from(self.rom) => {
+                receive(Receive::FunctionCommand)
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_CONVERT) => {
+                let until = now + self.resolution().conversion_time() as u64;
+                self.conversion = Some(until);
+                State::Busy { until }
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_SCRATCHPAD_READ) => {
+                transmit(&self.scratchpad(), false)
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_SCRATCHPAD_WRITE) => {
+                receive(Receive::WriteScratchpad)
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_SCRATCHPAD_COPY) => {
+                self.eeprom.copy_from_slice(&self.scratchpad[2..5]);
+                State::Busy {
+                    until: now + COPY_SCRATCHPAD_TIME_NS,
+                }
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_RECALL) => {
+                self.scratchpad[2..5].copy_from_slice(&self.eeprom);
+                State::Busy { until: now }
+            }
+            (Receive::FunctionCommand, COMMAND_MEMORY_POWER_SUPPLY_READ) => State::Transmit {
+                bytes: [!self.parasite_power as u8, 0, 0, 0, 0, 0, 0, 0, 0],
+                bits: 1,
+                index: 0,
+                function: false,
+            },
+            (Receive::WriteScratchpad, _) => {
+                let [high, low, configuration_register, ..] = value.to_le_bytes();
+                self.scratchpad[2] = high;
+                self.scratchpad[3] = low;
+                // Only the resolution bits are writable.
+                self.scratchpad[4] = match self.quirk {
+                    Some(Quirk::UnusedBits) => configuration_register,
+                    _ => configuration_register & 0b0110_0000 | 0b0001_1111,
+                };
+                State::Idle
+            }
+            _ => State::Idle,
+        };
+    }
+
+    fn rom_bit(&self, index: u32) -> bool {
+        u64::from(self.rom) >> index & 0x01 == 0x01
+    }
+}
+
+fn receive(command: Receive) -> State {
+    State::Receive {
+        command,
+        value: 0,
+        bits: 0,
+    }
+}
+
+fn transmit(data: &[u8], function: bool) -> State {
+    let mut bytes = [0; 9];
+    bytes[..data.len()].copy_from_slice(data);
+    State::Transmit {
+        bytes,
+        bits: data.len() as u32 * 8,
+        index: 0,
+        function,
+    }
+}
+
+/// Returns the bit at the index, least significant bit first.
+fn bit(bytes: &[u8], index: u32) -> bool {
+    bytes[index as usize / 8] >> (index % 8) & 0x01 == 0x01
+}
@@ -0,0 +1,187 @@
+use crate::Rom;
+
+/// Faults
+///
+/// Field conditions injected into the simulated bus.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Faults {
+    /// Flips the bit of a slot read by the master, with a probability of one
+    /// in the given number of slots.
+    pub bit_flips: Option<u32>,
+    /// Seed of the random bit flips.
+    pub seed: u64,
+    /// The devices don't send presence pulses.
+    pub missing_presence: bool,
+    /// The device leaves the bus after the given number of slots.
+    pub vanish: Option<(Rom, u32)>,
+    /// The bus is held low, e.g. shorted to ground.
+    pub stuck_low: bool,
+    /// The bus reads low for the given time (ns) after it is released, e.g.
+    /// with a long cable or a weak pull-up resistor.
+    pub rise_time: u64,
+}
+
+/// Random numbers (xorshift), reproducible from the seed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Random(u64);
+
+impl Random {
+    pub(super) fn new(seed: u64) -> Self {
+        // The state must not be 0.
+        Self(seed | 1)
+    }
+
+    /// Returns `true` with a probability of one in `n`.
+    pub(super) fn one_in(&mut self, n: u32) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0.is_multiple_of(n as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        detect_clone_symptom,
+        simulator::{
+            testing::{bus, device, noisy},
+            Delay, Device, Pin, Quirk,
+        },
+        CloneSymptom, Driver, Ds18b20, Ds18b20Error, Error, OneWireBus, RomCommands, Temperature,
+    };
+    use core::convert::Infallible;
+
+    fn ds18b20(device: &Device) -> Ds18b20 {
+        Ds18b20::new(*device.rom()).unwrap()
+    }
+
+    fn error(error: Ds18b20Error) -> Result<Temperature, Error<Infallible>> {
+        Err(Error::Ds18b20(error))
+    }
+
+    #[test]
+    fn bit_flips() {
+        let device = device(0x6F);
+        let bus = bus(&[device], noisy());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let mut crc = 0;
+        for _ in 0..100 {
+            match ds18b20(&device).read_scratchpad(&mut driver) {
+                // Never bogus data.
+                Ok(scratchpad) => assert_eq!(device.scratchpad(), scratchpad.to_bytes()),
+                Err(Error::Ds18b20(Ds18b20Error::UnexpectedCrc { .. })) => crc += 1,
+                Err(error) => panic!("{error:?}"),
+            }
+        }
+        assert_ne!(0, crc);
+    }
+
+    #[test]
+    fn missing_presence() {
+        let device = device(0x6F);
+        let faults = Faults {
+            missing_presence: true,
+            ..Default::default()
+        };
+        let bus = bus(&[device], faults);
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        assert_eq!(
+            error(Ds18b20Error::NoAttachedDevices),
+            ds18b20(&device).read_temperature(&mut driver)
+        );
+    }
+
+    #[test]
+    fn vanish() {
+        let devices = [device(0x6F), device(0x01)];
+        let faults = Faults {
+            // During the search of the first device.
+            vanish: Some((*devices[1].rom(), 60)),
+            ..Default::default()
+        };
+        let bus = bus(&devices, faults);
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let mut roms = driver.devices();
+        assert_eq!(
+            Some(Err(Error::Ds18b20(Ds18b20Error::UnexpectedResponse))),
+            roms.next()
+        );
+        assert_eq!(None, roms.next());
+    }
+
+    #[test]
+    fn stuck_low() {
+        let device = device(0x6F);
+        let faults = Faults {
+            stuck_low: true,
+            ..Default::default()
+        };
+        let bus = bus(&[device], faults);
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        assert_eq!(
+            error(Ds18b20Error::BusNotHigh),
+            ds18b20(&device).read_temperature(&mut driver)
+        );
+    }
+
+    #[test]
+    fn slow_rise() {
+        let device = device(0x6F);
+        let faults = Faults {
+            rise_time: 20_000,
+            ..Default::default()
+        };
+        let bus = bus(&[device], faults);
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        // Every read slot reads 0, the recall never completes.
+        assert_eq!(
+            Err(Error::Ds18b20(Ds18b20Error::Timeout)),
+            ds18b20(&device).recall_from_eeprom(&mut driver)
+        );
+        assert_eq!(
+            Err(Error::Ds18b20(Ds18b20Error::AllZeros)),
+            driver.read_rom()
+        );
+        let roms: std::vec::Vec<_> = driver.devices().collect();
+        assert_eq!(std::vec![Err(Error::Ds18b20(Ds18b20Error::AllZeros))], roms);
+    }
+
+    #[test]
+    fn power_on_reset() {
+        let device = device(0x6F);
+        let bus = bus(&[device], Default::default());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let ds18b20 = ds18b20(&device);
+        ds18b20.start_conversion(&mut driver).unwrap();
+        driver.delay(ds18b20.resolution().conversion_time());
+        assert_eq!(
+            Ok(Temperature::from_celsius(25)),
+            ds18b20.read_temperature(&mut driver)
+        );
+        bus.borrow_mut().devices_mut()[0].power_cycle();
+        assert_eq!(
+            error(Ds18b20Error::PowerOnReset),
+            ds18b20.read_temperature(&mut driver)
+        );
+    }
+
+    #[test]
+    fn clones() {
+        let devices = [
+            device(0x6F).with_quirk(Quirk::UnusedBits),
+            device(0x01).with_quirk(Quirk::Reserved([0xFF, 0xFF, 0x0C])),
+        ];
+        let bus = bus(&devices, Default::default());
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        assert_eq!(
+            Ok(CloneSymptom::UnusedBits),
+            detect_clone_symptom(&mut driver, &ds18b20(&devices[0]))
+        );
+        assert_eq!(
+            Ok(CloneSymptom::ReservedBytes),
+            detect_clone_symptom(&mut driver, &ds18b20(&devices[1]))
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Simulated 1-Wire bus, for testing without hardware.
+//!
+//! The [`Bus`] models the wired-AND of an open-drain line and any number of
+//! simulated DS18B20s in virtual time. The [`Pin`] and the [`Delay`] share
+//! the bus, so the real [`Driver`](crate::Driver) can run on it:
+//!
+//! - the master pulls the bus low and releases it through the pin, the delay
+//!   advances the time;
+//! - a low time of 480 μs or more is a reset, the devices answer with a
+//!   presence pulse 30 μs to 150 μs after the release;
+//! - a shorter low time is a slot, written as 1 if it is shorter than 30 μs.
+//!   A device sending a 0 pulls the bus low for 30 μs from the start of the
+//!   slot.
+//!
+//! [`Faults`] reproduce field conditions: bit flips, missing presence pulses,
+//! devices leaving the bus, a bus stuck low or slow to rise.
+
+pub use self::{
+    device::{Device, Quirk},
+    faults::Faults,
+};
+
+use self::faults::Random;
+use crate::Rom;
+use core::{cell::RefCell, convert::Infallible, ops::Range};
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType, InputPin, OutputPin},
+};
+use std::vec::Vec;
+
+/// Shortest reset pulse recognized by the devices.
+const RESET_NS: u64 = 480_000;
+/// Devices sample the written bit 30 μs after the start of the slot.
+const SAMPLE_NS: u64 = 30_000;
+/// A device sending a 0 holds the bus low for 30 μs.
+const HOLD_NS: u64 = 30_000;
+const PRESENCE_NS: Range<u64> = 30_000..150_000;
+
+/// Simulated bus
+#[derive(Clone, Debug, Default)]
+pub struct Bus {
+    /// Virtual time (ns).
+    now: u64,
+    /// The master pulls the bus low since this time.
+    low: Option<u64>,
+    /// The times the devices pull the bus low.
+    pulls: Vec<Range<u64>>,
+    devices: Vec<Device>,
+    /// The master released the bus at this time.
+    released: u64,
+    faults: Faults,
+    random: Random,
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns a bus with the devices connected.
+    pub fn with_devices(devices: &[Device]) -> Self {
+        let mut bus = Self::new();
+        bus.devices.extend_from_slice(devices);
+        bus
+    }
+
+    /// Connects the device to the bus.
+    pub fn attach(&mut self, device: Device) {
+        self.devices.push(device);
+    }
+
+    /// Disconnects the device from the bus.
+    pub fn detach(&mut self, rom: &Rom) -> Option<Device> {
+        let index = self.devices.iter().position(|device| device.rom() == rom)?;
+        Some(self.devices.remove(index))
+    }
+
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    pub fn devices_mut(&mut self) -> &mut [Device] {
+        &mut self.devices
+    }
+
+    pub fn faults(&self) -> &Faults {
+        &self.faults
+    }
+
+    /// Injects the faults, replacing the previous ones.
+    pub fn set_faults(&mut self, faults: Faults) {
+        self.faults = faults;
+        self.random = Random::new(faults.seed);
+    }
+
+    /// Returns the virtual time (ns).
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the level of the bus.
+    pub fn is_high(&self) -> bool {
+        let rise_time = self.faults.rise_time;
+        let pulled = |pull: &Range<u64>| pull.start <= self.now && self.now < pull.end + rise_time;
+        !self.faults.stuck_low
+            && self.low.is_none()
+            && self.now >= self.released + rise_time
+            && !self.pulls.iter().any(pulled)
+    }
+
+    fn set_low(&mut self) {
+        if self.low.is_some() {
+            return;
+        }
+        self.low = Some(self.now);
+        let now = self.now;
+        let rise_time = self.faults.rise_time;
+        self.pulls.retain(|pull| pull.end + rise_time > now);
+        if let Some((rom, slots)) = &mut self.faults.vanish {
+            match slots {
+                0 => {
+                    let rom = *rom;
+                    self.faults.vanish = None;
+                    self.detach(&rom);
+                }
+                slots => *slots -= 1,
+            }
+        }
+        for device in &mut self.devices {
+            if device.slot(now) {
+                self.pulls.push(now..now + HOLD_NS);
+            }
+        }
+        if let Some(one_in) = self.faults.bit_flips {
+            if self.random.one_in(one_in) {
+                // A device sending 0 reads as 1, and the other way around.
+                let len = self.pulls.len();
+                self.pulls.retain(|pull| pull.start != now);
+                if self.pulls.len() == len {
+                    self.pulls.push(now..now + HOLD_NS);
+                }
+            }
+        }
+    }
+
+    fn set_high(&mut self) {
+        let Some(low) = self.low.take() else {
+            return;
+        };
+        let now = self.now;
+        self.released = now;
+        if now - low >= RESET_NS {
+            self.pulls.clear();
+            for device in &mut self.devices {
+                if device.reset(now) && !self.faults.missing_presence {
+                    self.pulls
+                        .push(now + PRESENCE_NS.start..now + PRESENCE_NS.end);
+                }
+            }
+        } else {
+            let bit = now - low < SAMPLE_NS;
+            for device in &mut self.devices {
+                device.written(bit, now);
+            }
+        }
+    }
+
+    fn delay(&mut self, ns: u32) {
+        self.now += ns as u64;
+        for device in &mut self.devices {
+            device.update(self.now);
+        }
+    }
+}
+
+/// Open-drain pin on the simulated bus
+#[derive(Clone, Copy, Debug)]
+pub struct Pin<'a>(&'a RefCell<Bus>);
+
+impl<'a> Pin<'a> {
+    pub fn new(bus: &'a RefCell<Bus>) -> Self {
+        Self(bus)
+    }
+}
+
+impl ErrorType for Pin<'_> {
+    type Error = Infallible;
+}
+
+impl InputPin for Pin<'_> {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(self.0.borrow().is_high())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(!self.0.borrow().is_high())
+    }
+}
+
+impl OutputPin for Pin<'_> {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().set_low();
+        Ok(())
+    }
+
+    /// Releases the bus.
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().set_high();
+        Ok(())
+    }
+}
+
+/// Delay in the virtual time of the simulated bus
+#[derive(Clone, Copy, Debug)]
+pub struct Delay<'a>(&'a RefCell<Bus>);
+
+impl<'a> Delay<'a> {
+    pub fn new(bus: &'a RefCell<Bus>) -> Self {
+        Self(bus)
+    }
+}
+
+impl DelayNs for Delay<'_> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().delay(ns);
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for Delay<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().delay(ns);
+    }
+}
+
+mod device;
+mod faults;
+#[cfg(test)]
+pub(crate) mod testing;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        detect_clone_symptom,
+        scratchpad::{Resolution, Triggers},
+        CloneSymptom, Driver, Ds18b20, Ds18b20Error, Error, MemoryCommands, OneWireBus,
+        PowerSupply, Rom, RomCommands, Temperature,
+    };
+
+    fn bus() -> RefCell<Bus> {
+        let mut bus = Bus::new();
+        for (serial_number, celsius) in [
+            ([0x6F, 0x29, 0x14, 0x0B, 0x00, 0x00], 72),
+            ([0x01, 0x29, 0x14, 0x0B, 0x00, 0x00], -10),
+            ([0x6F, 0x2A, 0x14, 0x0B, 0x00, 0x00], 80),
+        ] {
+            let device = Device::with_serial_number(serial_number)
+                .with_temperature(Temperature::from_celsius(celsius));
+            bus.attach(device);
+        }
+        RefCell::new(bus)
+    }
+
+    #[test]
+    fn search() {
+        let bus = bus();
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        let roms: Vec<Rom> = driver.devices().map(Result::unwrap).collect();
+        let mut expected: Vec<Rom> = bus
+            .borrow()
+            .devices()
+            .iter()
+            .map(|device| *device.rom())
+            .collect();
+        // The search chooses 0 first, starting from the least significant bit.
+        expected.sort_by_key(|&rom| u64::from(rom).reverse_bits());
+        assert_eq!(expected, roms);
+        // No alarms before a conversion.
+        assert_eq!(0, driver.alarms().count());
+        let devices: Vec<_> = roms.iter().map(|&rom| Ds18b20::new(rom).unwrap()).collect();
+        let temperatures: Vec<_> = driver
+            .convert_all(&devices)
+            .unwrap()
+            .map(|(_, temperature)| temperature.unwrap().to_millidegrees_celsius() / 1_000)
+            .collect();
+        assert_eq!(std::vec![-10, 80, 72], temperatures);
+        // TH 75 °C, TL 70 °C: at or out of the triggers.
+        let alarms: Vec<Rom> = driver.alarms().map(Result::unwrap).collect();
+        assert_eq!(std::vec![roms[0], roms[1]], alarms);
+    }
+
+    #[test]
+    fn device() {
+        let bus = RefCell::new(Bus::with_devices(&[testing::device(0x6F)]));
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        assert_eq!(Ok(true), driver.initialization());
+        let rom = driver.read_rom().unwrap();
+        assert_eq!(bus.borrow().devices()[0].rom(), &rom);
+        let mut device = Ds18b20::new(rom).unwrap();
+        assert_eq!(
+            Err(Error::Ds18b20(Ds18b20Error::PowerOnReset)),
+            device.read_temperature(&mut driver)
+        );
+        device
+            .set_resolution(&mut driver, Resolution::Nine)
+            .unwrap();
+        device
+            .set_alarm_thresholds(&mut driver, Triggers { high: 30, low: -5 })
+            .unwrap();
+        device.start_conversion(&mut driver).unwrap();
+        driver.delay(Resolution::Nine.conversion_time());
+        assert_eq!(
+            Ok(Temperature::from_celsius(25)),
+            device.read_temperature(&mut driver)
+        );
+        device.save_to_eeprom(&mut driver).unwrap();
+        assert_eq!([30, -5i8 as u8, 0x1F], bus.borrow().devices()[0].eeprom());
+        device
+            .set_resolution(&mut driver, Resolution::Twelve)
+            .unwrap();
+        device.recall_from_eeprom(&mut driver).unwrap();
+        assert_eq!(Resolution::Nine, device.resolution());
+        let mut scratchpad = device.read_scratchpad(&mut driver).unwrap();
+        scratchpad.configuration_register.resolution = Resolution::Ten;
+        device.write_scratchpad(&mut driver, scratchpad).unwrap();
+        assert_eq!(Resolution::Ten, device.resolution());
+        assert_eq!(
+            Ok(PowerSupply::External),
+            device.read_power_supply(&mut driver)
+        );
+        assert_eq!(
+            Ok(CloneSymptom::NoSymptom),
+            detect_clone_symptom(&mut driver, &device)
+        );
+    }
+
+    #[test]
+    fn parasite_power() {
+        let bus = bus();
+        let parasite =
+            Device::with_serial_number([0x01, 0x2A, 0x14, 0x0B, 0x00, 0x00]).with_parasite_power();
+        bus.borrow_mut().attach(parasite);
+        let mut driver = Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap();
+        assert_eq!(Ok(PowerSupply::Parasite), driver.read_power_supply_all());
+        assert!(driver.parasite_power());
+        bus.borrow_mut().detach(parasite.rom());
+        assert_eq!(Ok(PowerSupply::External), driver.read_power_supply_all());
+        assert!(!driver.parasite_power());
+    }
+}
@@ -0,0 +1,27 @@
+//! Buses and devices shared by the tests running on the simulator.
+
+use super::{Bus, Device, Faults};
+use core::cell::RefCell;
+
+/// Seed of the random faults, the tests expect its sequence.
+pub(crate) const SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Genuine DS18B20 told apart by the first serial number byte.
+pub(crate) fn device(serial_number: u8) -> Device {
+    Device::with_serial_number([serial_number, 0x29, 0x14, 0x0B, 0x00, 0x00])
+}
+
+pub(crate) fn bus(devices: &[Device], faults: Faults) -> RefCell<Bus> {
+    let mut bus = Bus::with_devices(devices);
+    bus.set_faults(faults);
+    RefCell::new(bus)
+}
+
+/// One bit flip in 200 slots.
+pub(crate) fn noisy() -> Faults {
+    Faults {
+        bit_flips: Some(200),
+        seed: SEED,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,343 @@
+//! 1-Wire master on a SPI bus.
+//!
+//! MOSI drives the bus through an open-drain buffer (or a diode) and MISO
+//! reads it back. Every SPI bit is a time step of the 1-Wire slot, so the
+//! timing comes from the SPI clock instead of a delay:
+//!
+//! - slot: MOSI low for the low time of the slot (A for a write 1 or a read, C
+//!   for a write 0, H for a reset), then high until the end of the slot;
+//! - sample: the MISO bit at the sample time (A + E for a read, H + I for a
+//!   reset) is the bus level;
+//! - delay: MOSI high for the delay.
+//!
+//! A slot is padded to whole bytes with high bits, which adds to the recovery
+//! time.
+
+use crate::{error::ConfigurationError, Configuration, Ds18b20Error, Error, OneWireBus};
+use embedded_hal::spi::{ErrorType, SpiBus};
+
+/// The bytes sent in one transfer.
+///
+/// A bit slot should fit in one transfer, otherwise the gap between the
+/// transfers stretches it, e.g. a standard slot (70 µs) up to a 14 MHz clock.
+const BUFFER: usize = 128;
+
+const NS_PER_S: u64 = 1_000_000_000;
+
+/// SPI 1-Wire master
+#[derive(Debug)]
+pub struct Spi<T: ErrorType> {
+    spi: T,
+    frequency: u32,
+    configuration: Configuration,
+    parasite_power: bool,
+    /// Error of a delay, returned by the next operation.
+    error: Option<T::Error>,
+}
+
+impl<T: ErrorType> Spi<T> {
+    /// Uses the SPI bus running at the given clock frequency (Hz).
+    ///
+    /// The clock has to resolve every time of the slots, see
+    /// [`Spi::set_configuration`].
+    pub fn new(spi: T, frequency: u32) -> Result<Self, ConfigurationError> {
+        let mut spi = Self {
+            spi,
+            frequency,
+            configuration: Default::default(),
+            parasite_power: false,
+            error: None,
+        };
+        spi.set_configuration(Default::default())?;
+        Ok(spi)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.spi
+    }
+
+    pub fn configuration(&self) -> &Configuration {
+        &self.configuration
+    }
+
+    /// Sets the timings of the slots.
+    ///
+    /// Every time used by the slots (all but G) has to last at least one SPI
+    /// bit, otherwise it would be dropped from the slot.
+    pub fn set_configuration(
+        &mut self,
+        configuration: Configuration,
+    ) -> Result<(), ConfigurationError> {
+        let Configuration {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            h,
+            i,
+            j,
+            ..
+        } = configuration;
+        let times = [
+            ("A", a),
+            ("B", b),
+            ("C", c),
+            ("D", d),
+            ("E", e),
+            ("F", f),
+            ("H", h),
+            ("I", i),
+            ("J", j),
+        ];
+        for (field, ns) in times {
+            if self.bits(ns) == 0 {
+                Err(ConfigurationError::ClockTooSlow {
+                    field,
+                    frequency: self.frequency,
+                    min: NS_PER_S.div_ceil(ns.max(1) as u64) as u32,
+                })?;
+            }
+        }
+        self.configuration = configuration;
+        Ok(())
+    }
+
+    /// Returns the number of whole SPI bits in the given time (ns).
+    fn bits(&self, ns: u32) -> usize {
+        (ns as u64 * self.frequency as u64 / NS_PER_S) as usize
+    }
+}
+
+impl<T: SpiBus> Spi<T> {
+    /// Sends a slot, low for the given time then high until the end.
+    ///
+    /// Returns the bus level at the sample time and at the end of the slot.
+    fn slot(&mut self, low: u32, sample: u32, end: u32) -> Result<(bool, bool), Error<T::Error>> {
+        if let Some(error) = self.error.take() {
+            Err(Error::Bus(error))?;
+        }
+        let low = self.bits(low);
+        let sample = self.bits(sample);
+        let len = self.bits(end).max(sample + 1).div_ceil(8);
+        let (mut sampled, mut ended) = (true, true);
+        let mut buffer = [0; BUFFER];
+        for start in (0..len).step_by(BUFFER) {
+            let bytes = &mut buffer[..BUFFER.min(len - start)];
+            for (index, byte) in bytes.iter_mut().enumerate() {
+                let first = (start + index) * 8;
+                // MSB first, the low bits are the leading zeros.
+                *byte = match low.saturating_sub(first) {
+                    0 => 0xFF,
+                    bits @ 1..=7 => 0xFF >> bits,
+                    _ => 0x00,
+                };
+            }
+            self.spi.transfer_in_place(bytes).map_err(Error::Bus)?;
+            let level = |bit: usize| bytes[bit / 8 - start] & 0x80 >> (bit % 8) != 0;
+            if (start * 8..(start + bytes.len()) * 8).contains(&sample) {
+                sampled = level(sample);
+            }
+            if start + bytes.len() == len {
+                ended = level(len * 8 - 1);
+            }
+        }
+        Ok((sampled, ended))
+    }
+}
+
+impl<T: SpiBus> OneWireBus for Spi<T> {
+    type Error = T::Error;
+
+    fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+        let Configuration { h, i, j, .. } = self.configuration;
+        let (presence, high) = self.slot(h, h + i, h + i + j)?;
+        if !high {
+            Err(Ds18b20Error::BusNotHigh)?;
+        }
+        Ok(!presence)
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+        let Configuration { a, e, f, .. } = self.configuration;
+        Ok(self.slot(a, a + e, a + e + f)?.0)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+        let Configuration { a, b, c, d, .. } = self.configuration;
+        if bit {
+            self.slot(a, 0, a + b)?;
+        } else {
+            self.slot(c, 0, c + d)?;
+        }
+        Ok(())
+    }
+
+    /// Clocks out high bits for the given time.
+    ///
+    /// An SPI error is returned by the next operation.
+    fn delay(&mut self, ns: u32) {
+        let bytes = [0xFF; BUFFER];
+        let mut len = self.bits(ns).div_ceil(8);
+        while len > 0 {
+            let chunk = len.min(BUFFER);
+            if let Err(error) = self.spi.write(&bytes[..chunk]) {
+                self.error = Some(error);
+                return;
+            }
+            len -= chunk;
+        }
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::spi::ErrorKind;
+
+    /// Echoes MOSI, a device pulls the bus low for the presence pulse and the
+    /// 0 bits read.
+    #[derive(Default)]
+    struct Line {
+        presence: bool,
+        zero: bool,
+        sent: usize,
+    }
+
+    impl ErrorType for Line {
+        type Error = Infallible;
+    }
+
+    impl SpiBus for Line {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+            words.fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+            self.sent += words.len();
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Infallible> {
+            read.copy_from_slice(write);
+            self.transfer_in_place(read)
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+            self.sent += words.len();
+            // At 1 MHz, a reset frame is 120 bytes and a bit slot 9 bytes.
+            if words.len() > 100 && self.presence {
+                // 536 µs to 656 µs
+                words[67..82].fill(0x00);
+            } else if words.len() < 100 && self.zero && words[0] == 0x03 {
+                words[..2].fill(0x00);
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    fn spi(line: Line) -> Spi<Line> {
+        Spi::new(line, 1_000_000).unwrap()
+    }
+
+    #[test]
+    fn initialization() {
+        let line = Line {
+            presence: true,
+            ..Default::default()
+        };
+        assert_eq!(Ok(true), spi(line).initialization());
+        assert_eq!(Ok(false), spi(Line::default()).initialization());
+    }
+
+    #[test]
+    fn bits() {
+        let mut master = spi(Line::default());
+        assert_eq!(Ok(true), master.read_bit());
+        assert_eq!(Ok(()), master.write_bit(false));
+        // 70 µs slots
+        assert_eq!(9 + 9, master.spi.sent);
+        master.delay(10_000_000);
+        assert_eq!(18 + 1_250, master.spi.sent);
+        let line = Line {
+            zero: true,
+            ..Default::default()
+        };
+        assert_eq!(Ok(0x00), spi(line).read_byte());
+    }
+
+    /// Fails the writes only, i.e. the delays.
+    struct Broken;
+
+    impl ErrorType for Broken {
+        type Error = ErrorKind;
+    }
+
+    impl SpiBus for Broken {
+        fn read(&mut self, _words: &mut [u8]) -> Result<(), ErrorKind> {
+            Ok(())
+        }
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), ErrorKind> {
+            Err(ErrorKind::Other)
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), ErrorKind> {
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), ErrorKind> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ErrorKind> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Some(ConfigurationError::ClockTooSlow {
+                field: "A",
+                frequency: 100_000,
+                min: 166_667,
+            }),
+            Spi::new(Line::default(), 100_000).err()
+        );
+        let mut master = spi(Line::default());
+        let configuration = Configuration {
+            e: 500,
+            ..Configuration::standard()
+        };
+        assert_eq!(
+            Err(ConfigurationError::ClockTooSlow {
+                field: "E",
+                frequency: 1_000_000,
+                min: 2_000_000,
+            }),
+            master.set_configuration(configuration)
+        );
+        assert_eq!(&Configuration::standard(), master.configuration());
+        assert_eq!(Ok(()), master.set_configuration(Configuration::overdrive()));
+        let mut master = Spi::new(Broken, 1_000_000).unwrap();
+        master.delay(10_000);
+        assert_eq!(Err(Error::Bus(ErrorKind::Other)), master.read_bit());
+        assert!(master.read_bit().is_ok());
+    }
+}
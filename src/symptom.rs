@@ -0,0 +1,204 @@
+use crate::{
+    commands::memory::{COMMAND_MEMORY_SCRATCHPAD_READ, COMMAND_MEMORY_SCRATCHPAD_WRITE},
+    crc8::check,
+    scratchpad::ConfigurationRegister,
+    Ds18b20, Error, MemoryCommands, OneWireBus, Rom, Scratchpad,
+};
+
+/// Configuration register bits which are not used by a genuine DS18B20. They
+/// always read as `1` for bits 0 through 4 and `0` for bit 7.
+const UNUSED_BITS: u8 = 0b1001_1111;
+
+/// Clone symptom
+///
+/// Clones behave differently from genuine DS18B20s in ways that matter for the
+/// driver (reserved bytes, power-on configuration, parasite power, alarm
+/// search). The symptom is the first check the device fails, comparing its
+/// ROM, its scratchpad and its response to undocumented writes with a genuine
+/// DS18B20. It doesn't identify the clone family: several families share a
+/// symptom, and a clone may pass every check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CloneSymptom {
+    /// Passes every check, behaves like a genuine Maxim (Analog Devices)
+    /// DS18B20.
+    NoSymptom,
+    /// The two most significant serial number bytes are not `0x00`.
+    RomPattern,
+    /// The unused configuration register bits are stored.
+    UnusedBits,
+    /// Reserved scratchpad byte 5 is not `0xFF` or byte 7 is not `0x10`.
+    ReservedBytes,
+    /// The temperature triggers are not stored as written.
+    Unknown,
+}
+
+impl CloneSymptom {
+    /// Checks a device from its ROM and scratchpad only, without disturbing
+    /// it.
+    ///
+    /// Genuine DS18B20s have `0x00` in the two most significant serial number
+    /// bytes and read reserved bytes 5 and 7 as `0xFF` and `0x10`.
+    pub fn classify(rom: &Rom, scratchpad: &Scratchpad) -> Self {
+        if scratchpad.reserved[0] != 0xFF || scratchpad.reserved[2] != 0x10 {
+            Self::ReservedBytes
+        } else if rom.serial_number[4..] != [0x00, 0x00] {
+            Self::RomPattern
+        } else {
+            Self::NoSymptom
+        }
+    }
+}
+
+/// Detects the symptom of a clone of the device.
+///
+/// Besides [`CloneSymptom::classify`], probes the device by writing the unused
+/// configuration register bits. The scratchpad is restored afterwards, even
+/// if the probe fails, the EEPROM is not touched.
+pub fn detect_clone_symptom<B: OneWireBus>(
+    bus: &mut B,
+    device: &Ds18b20,
+) -> Result<CloneSymptom, Error<B::Error>> {
+    let scratchpad = device.read_scratchpad(bus)?;
+    let symptom = CloneSymptom::classify(device.rom(), &scratchpad);
+    if symptom == CloneSymptom::ReservedBytes {
+        return Ok(symptom);
+    }
+    // Write the unused bits inverted and read them back.
+    let configuration_register = u8::from(scratchpad.configuration_register) ^ UNUSED_BITS;
+    let probe: Result<_, Error<B::Error>> = (|| {
+        device.transaction().begin(bus)?;
+        bus.write_command(COMMAND_MEMORY_SCRATCHPAD_WRITE)?;
+        bus.write_bytes(&[
+            scratchpad.triggers.high as _,
+            scratchpad.triggers.low as _,
+            configuration_register,
+        ])?;
+        device.transaction().begin(bus)?;
+        bus.write_command(COMMAND_MEMORY_SCRATCHPAD_READ)?;
+        let mut bytes = [0; 9];
+        bus.read_bytes(&mut bytes)?;
+        Ok(bytes)
+    })();
+    // Restore the scratchpad even if the probe failed.
+    let restore = device
+        .transaction()
+        .begin(bus)
+        .and_then(|_| bus.write_scratchpad(scratchpad));
+    let bytes = probe?;
+    restore?;
+    check(&bytes).map_err(|error| bus.report(error.into()))?;
+    if bytes[2..4]
+        != [
+            scratchpad.triggers.high as u8,
+            scratchpad.triggers.low as u8,
+        ]
+    {
+        return Ok(CloneSymptom::Unknown);
+    }
+    Ok(match ConfigurationRegister::try_from(bytes[4]) {
+        Ok(_) => symptom,
+        Err(_) => CloneSymptom::UnusedBits,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        let genuine = Rom {
+            family_code: 0x28,
+            serial_number: [0x6F, 0x29, 0x14, 0x0B, 0x00, 0x00],
+            crc: 0x00,
+        };
+        let clone = Rom {
+            family_code: 0x28,
+            serial_number: [0xFF, 0x64, 0x1E, 0x0F, 0x85, 0x92],
+            crc: 0x00,
+        };
+        let mut scratchpad = Scratchpad {
+            reserved: [0xFF, 0x0C, 0x10],
+            ..Default::default()
+        };
+        assert_eq!(
+            CloneSymptom::NoSymptom,
+            CloneSymptom::classify(&genuine, &scratchpad)
+        );
+        assert_eq!(
+            CloneSymptom::RomPattern,
+            CloneSymptom::classify(&clone, &scratchpad)
+        );
+        scratchpad.reserved = [0x0C, 0x0C, 0x10];
+        assert_eq!(
+            CloneSymptom::ReservedBytes,
+            CloneSymptom::classify(&genuine, &scratchpad)
+        );
+    }
+
+    /// Reports no presence pulse on the given initialization.
+    #[cfg(feature = "simulator")]
+    struct Flaky<B> {
+        bus: B,
+        initializations: usize,
+        failure: usize,
+    }
+
+    #[cfg(feature = "simulator")]
+    impl<B: OneWireBus> OneWireBus for Flaky<B> {
+        type Error = B::Error;
+
+        fn initialization(&mut self) -> Result<bool, Error<B::Error>> {
+            self.initializations += 1;
+            let presence = self.bus.initialization()?;
+            Ok(presence && self.initializations != self.failure)
+        }
+
+        fn read_bit(&mut self) -> Result<bool, Error<B::Error>> {
+            self.bus.read_bit()
+        }
+
+        fn write_bit(&mut self, bit: bool) -> Result<(), Error<B::Error>> {
+            self.bus.write_bit(bit)
+        }
+
+        fn delay(&mut self, ns: u32) {
+            self.bus.delay(ns)
+        }
+
+        fn parasite_power(&self) -> bool {
+            self.bus.parasite_power()
+        }
+
+        fn set_parasite_power(&mut self, parasite_power: bool) {
+            self.bus.set_parasite_power(parasite_power)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simulator")]
+    fn restore() {
+        use crate::{
+            error::Ds18b20Error,
+            simulator::{testing::device, Bus, Delay, Pin, Quirk},
+            Driver,
+        };
+        use core::cell::RefCell;
+
+        let device = device(0x6F).with_quirk(Quirk::UnusedBits);
+        let bus = RefCell::new(Bus::with_devices(&[device]));
+        let mut flaky = Flaky {
+            bus: Driver::new(Pin::new(&bus), Delay::new(&bus)).unwrap(),
+            initializations: 0,
+            // The read back, after the probe write.
+            failure: 3,
+        };
+        let ds18b20 = Ds18b20::new(*device.rom()).unwrap();
+        assert_eq!(
+            Err(Error::Ds18b20(Ds18b20Error::NoAttachedDevices)),
+            detect_clone_symptom(&mut flaky, &ds18b20)
+        );
+        assert_eq!(4, flaky.initializations);
+        assert_eq!(device.scratchpad(), bus.borrow().devices()[0].scratchpad());
+    }
+}
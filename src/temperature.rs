@@ -0,0 +1,208 @@
+use crate::{error::Ds18b20Error, scratchpad::Resolution};
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+};
+
+/// Temperature
+///
+/// Fixed-point temperature in 1/16 °C, the format of the DS18B20 temperature
+/// register. The arithmetic saturates at the bounds of the raw value.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Temperature(i16);
+
+impl Temperature {
+    /// The lowest temperature the DS18B20 can measure, -55 °C.
+    pub const MIN: Self = Self::from_celsius(-55);
+
+    /// The highest temperature the DS18B20 can measure, +125 °C.
+    pub const MAX: Self = Self::from_celsius(125);
+
+    /// The power-on reset value of the temperature register, +85 °C.
+    pub const POWER_ON_RESET: Self = Self(0x0550);
+
+    /// Creates a temperature from the raw register value in 1/16 °C.
+    pub const fn from_raw(raw: i16) -> Self {
+        Self(raw)
+    }
+
+    /// Creates a temperature from whole degrees Celsius, e.g. a temperature
+    /// trigger.
+    pub const fn from_celsius(degrees: i8) -> Self {
+        Self((degrees as i16) << 4)
+    }
+
+    /// Creates a temperature from the temperature register.
+    ///
+    /// The bits below the resolution are undefined and are cleared.
+    pub fn from_register(msb: u8, lsb: u8, resolution: Resolution) -> Self {
+        let mask = match resolution {
+            Resolution::Nine => !0b111,
+            Resolution::Ten => !0b11,
+            Resolution::Eleven => !0b1,
+            Resolution::Twelve => !0,
+        };
+        Self(i16::from_be_bytes([msb, lsb]) & mask)
+    }
+
+    /// Checks that the temperature is within the measurement range.
+    ///
+    /// The power-on reset value is a valid temperature, it is told apart from
+    /// a measured +85 °C by the rest of the scratchpad, see
+    /// [`Scratchpad::measured_temperature`](crate::Scratchpad::measured_temperature).
+    pub fn check(self) -> Result<Self, Ds18b20Error> {
+        if self < Self::MIN || self > Self::MAX {
+            Err(Ds18b20Error::OutOfRange { temperature: self })
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Returns the raw value in 1/16 °C.
+    pub const fn raw(&self) -> i16 {
+        self.0
+    }
+
+    /// Returns the temperature in millidegrees Celsius, the fraction of a
+    /// millidegree is dropped.
+    pub const fn to_millidegrees_celsius(&self) -> i32 {
+        self.0 as i32 * 1000 / 16
+    }
+
+    /// Returns the temperature in millidegrees Fahrenheit, the fraction of a
+    /// millidegree is dropped.
+    pub const fn to_millidegrees_fahrenheit(&self) -> i32 {
+        self.0 as i32 * 9000 / 80 + 32_000
+    }
+}
+
+impl From<Temperature> for f32 {
+    fn from(value: Temperature) -> Self {
+        value.0 as f32 / 16.0
+    }
+}
+
+impl Add for Temperature {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl AddAssign for Temperature {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 = self.0.saturating_add(rhs.0);
+    }
+}
+
+impl Sub for Temperature {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl SubAssign for Temperature {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 = self.0.saturating_sub(rhs.0);
+    }
+}
+
+impl Neg for Temperature {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(self.0.saturating_neg())
+    }
+}
+
+/// Formats the exact value in degrees Celsius, e.g. `-10.125°C`.
+impl Display for Temperature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let raw = self.0.unsigned_abs();
+        let integer = raw >> 4;
+        // 1/16 = 0.0625, so 4 decimal digits are exact.
+        let mut fraction = (raw & 0xF) * 625;
+        let mut width = 4;
+        while width > 1 && fraction.is_multiple_of(10) {
+            fraction /= 10;
+            width -= 1;
+        }
+        write!(f, "{sign}{integer}.{fraction:0width$}°C")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let temperature = Temperature::from_raw(0x0191);
+        assert_eq!(25_062, temperature.to_millidegrees_celsius());
+        assert_eq!(77_112, temperature.to_millidegrees_fahrenheit());
+        assert_eq!(25.0625, f32::from(temperature));
+        let temperature = Temperature::from_raw(-0x0370);
+        assert_eq!(-55_000, temperature.to_millidegrees_celsius());
+        assert_eq!(-67_000, temperature.to_millidegrees_fahrenheit());
+        assert_eq!(
+            Temperature::from_raw(0x07D0),
+            Temperature::from_celsius(125)
+        );
+        assert_eq!(
+            Temperature::from_raw(0x0190),
+            Temperature::from_register(0x01, 0x97, Resolution::Nine),
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(Ok(Temperature::MIN), Temperature::MIN.check());
+        assert_eq!(Ok(Temperature::MAX), Temperature::MAX.check());
+        assert_eq!(
+            Ok(Temperature::POWER_ON_RESET),
+            Temperature::POWER_ON_RESET.check(),
+        );
+        assert_eq!(
+            Err(Ds18b20Error::OutOfRange {
+                temperature: Temperature::from_raw(0x07D1)
+            }),
+            Temperature::from_raw(0x07D1).check(),
+        );
+        assert_eq!(
+            Err(Ds18b20Error::OutOfRange {
+                temperature: Temperature::from_raw(-0x0371)
+            }),
+            Temperature::from_raw(-0x0371).check(),
+        );
+    }
+
+    #[test]
+    fn saturation() {
+        let max = Temperature::from_raw(i16::MAX);
+        let min = Temperature::from_raw(i16::MIN);
+        assert_eq!(max, max + Temperature::MAX);
+        assert_eq!(min, min - Temperature::MAX);
+        assert_eq!(max, -min);
+        let mut temperature = Temperature::MAX;
+        temperature -= min;
+        assert_eq!(max, temperature);
+        temperature += max;
+        assert_eq!(max, temperature);
+    }
+
+    #[test]
+    fn display() {
+        extern crate std;
+        use std::string::ToString;
+
+        assert_eq!("125.0°C", Temperature::from_raw(0x07D0).to_string());
+        assert_eq!("25.0625°C", Temperature::from_raw(0x0191).to_string());
+        assert_eq!("10.125°C", Temperature::from_raw(0x00A2).to_string());
+        assert_eq!("-0.5°C", Temperature::from_raw(-0x0008).to_string());
+        assert_eq!("-10.125°C", Temperature::from_raw(-0x00A2).to_string());
+    }
+}
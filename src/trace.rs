@@ -0,0 +1,611 @@
+//! Bus trace recorder, a logic analyzer for the [`Driver`](crate::Driver).
+//!
+//! [`TracePin`] and [`TraceDelay`] wrap the pin and the delay of the driver and
+//! record the edges and the samples of the pin into a [`Recorder`]. The
+//! timestamps are the sum of the delays (ns). The oldest records are dropped
+//! once the recorder is full.
+//!
+//! The records are decoded into resets, bits, bytes and commands at standard
+//! speed, see [`Recorder::events`]. On std targets they can be written as a VCD
+//! file or as a protocol log.
+
+use crate::commands::{
+    memory::{
+        COMMAND_MEMORY_CONVERT, COMMAND_MEMORY_POWER_SUPPLY_READ, COMMAND_MEMORY_RECALL,
+        COMMAND_MEMORY_SCRATCHPAD_COPY, COMMAND_MEMORY_SCRATCHPAD_READ,
+        COMMAND_MEMORY_SCRATCHPAD_WRITE,
+    },
+    rom::{
+        COMMAND_ALARM_SEARCH, COMMAND_ROM_MATCH, COMMAND_ROM_OVERDRIVE_MATCH,
+        COMMAND_ROM_OVERDRIVE_SKIP, COMMAND_ROM_READ, COMMAND_ROM_SEARCH, COMMAND_ROM_SKIP,
+    },
+};
+use core::{
+    cell::RefCell,
+    fmt::{self, Display, Formatter},
+};
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType, InputPin, OutputPin},
+};
+
+/// Shortest reset pulse.
+const RESET_NS: u64 = 480_000;
+/// Longest write 1 (or read) low time.
+const WRITE_ONE_NS: u64 = 15_000;
+/// Latest sample of a bit slot, from the start of the slot.
+const SLOT_SAMPLE_NS: u64 = 60_000;
+/// Latest presence sample, from the end of the reset pulse.
+const PRESENCE_SAMPLE_NS: u64 = 300_000;
+
+/// Record
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Record {
+    /// The master pulls the bus low.
+    Low(u64),
+    /// The master releases the bus.
+    High(u64),
+    /// The master reads the bus level.
+    Sample(u64, bool),
+}
+
+impl Record {
+    pub fn time(&self) -> u64 {
+        match *self {
+            Record::Low(time) | Record::High(time) | Record::Sample(time, _) => time,
+        }
+    }
+}
+
+/// Recorder
+///
+/// Ring buffer of the last `N` records.
+#[derive(Clone, Debug)]
+pub struct Recorder<const N: usize> {
+    records: [Record; N],
+    start: usize,
+    len: usize,
+    now: u64,
+}
+
+impl<const N: usize> Recorder<N> {
+    pub const fn new() -> Self {
+        Self {
+            records: [Record::High(0); N],
+            start: 0,
+            len: 0,
+            now: 0,
+        }
+    }
+
+    /// Returns the time of the trace (ns).
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the records, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        (0..self.len).map(|index| self.records[(self.start + index) % N])
+    }
+
+    /// Returns the decoded events, oldest first.
+    pub fn events(&self) -> Events<impl Iterator<Item = Record> + '_> {
+        Events {
+            records: self.records().peekable(),
+            phase: Phase::Idle,
+            bits: Bits::default(),
+            search: (0, 0),
+            pending: None,
+        }
+    }
+
+    /// Drops all records.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    fn push(&mut self, record: Record) {
+        if N == 0 {
+            return;
+        }
+        if self.len == N {
+            self.records[self.start] = record;
+            self.start = (self.start + 1) % N;
+        } else {
+            self.records[(self.start + self.len) % N] = record;
+            self.len += 1;
+        }
+    }
+}
+
+impl<const N: usize> Default for Recorder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> Recorder<N> {
+    /// Writes the records as a VCD (value change dump) file: the level driven
+    /// by the master and the level it samples.
+    pub fn write_vcd(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        writeln!(writer, "$timescale 1 ns $end")?;
+        writeln!(writer, "$scope module one_wire $end")?;
+        writeln!(writer, "$var wire 1 m master $end")?;
+        writeln!(writer, "$var wire 1 s sample $end")?;
+        writeln!(writer, "$upscope $end")?;
+        writeln!(writer, "$enddefinitions $end")?;
+        for record in self.records() {
+            match record {
+                Record::Low(time) => writeln!(writer, "#{time}\n0m")?,
+                Record::High(time) => writeln!(writer, "#{time}\n1m")?,
+                Record::Sample(time, level) => writeln!(writer, "#{time}\n{}s", level as u8)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the decoded events, one per line.
+    pub fn write_log(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        for event in self.events() {
+            writeln!(writer, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Pin recording its edges and samples
+#[derive(Debug)]
+pub struct TracePin<'a, T, const N: usize> {
+    pin: T,
+    recorder: &'a RefCell<Recorder<N>>,
+}
+
+impl<'a, T, const N: usize> TracePin<'a, T, N> {
+    pub fn new(pin: T, recorder: &'a RefCell<Recorder<N>>) -> Self {
+        Self { pin, recorder }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.pin
+    }
+
+    fn record(&self, record: impl FnOnce(u64) -> Record) {
+        let mut recorder = self.recorder.borrow_mut();
+        let record = record(recorder.now);
+        recorder.push(record);
+    }
+}
+
+impl<T: ErrorType, const N: usize> ErrorType for TracePin<'_, T, N> {
+    type Error = T::Error;
+}
+
+impl<T: InputPin, const N: usize> InputPin for TracePin<'_, T, N> {
+    fn is_high(&mut self) -> Result<bool, T::Error> {
+        let level = self.pin.is_high()?;
+        self.record(|time| Record::Sample(time, level));
+        Ok(level)
+    }
+
+    fn is_low(&mut self) -> Result<bool, T::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+impl<T: OutputPin, const N: usize> OutputPin for TracePin<'_, T, N> {
+    fn set_low(&mut self) -> Result<(), T::Error> {
+        self.pin.set_low()?;
+        self.record(Record::Low);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), T::Error> {
+        self.pin.set_high()?;
+        self.record(Record::High);
+        Ok(())
+    }
+}
+
+/// Delay advancing the time of the trace
+#[derive(Debug)]
+pub struct TraceDelay<'a, U, const N: usize> {
+    delay: U,
+    recorder: &'a RefCell<Recorder<N>>,
+}
+
+impl<'a, U, const N: usize> TraceDelay<'a, U, N> {
+    pub fn new(delay: U, recorder: &'a RefCell<Recorder<N>>) -> Self {
+        Self { delay, recorder }
+    }
+
+    pub fn into_inner(self) -> U {
+        self.delay
+    }
+}
+
+impl<U: DelayNs, const N: usize> DelayNs for TraceDelay<'_, U, N> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay.delay_ns(ns);
+        self.recorder.borrow_mut().now += ns as u64;
+    }
+}
+
+/// Event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    Reset {
+        presence: bool,
+    },
+    RomCommand(u8),
+    FunctionCommand(u8),
+    Write(u8),
+    Read(u8),
+    /// Bits which don't make a whole byte, least significant bit first.
+    Bits {
+        read: bool,
+        value: u8,
+        len: u8,
+    },
+    /// A search pass, the ROM of the followed branch.
+    Search {
+        rom: u64,
+    },
+}
+
+/// Event with the time (ns) of its first slot
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Traced {
+    pub time: u64,
+    pub event: Event,
+}
+
+impl Display for Traced {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>8}.{:03} µs  ", self.time / 1_000, self.time % 1_000)?;
+        match self.event {
+            Event::Reset { presence: true } => write!(f, "reset, presence"),
+            Event::Reset { presence: false } => write!(f, "reset, no presence"),
+            Event::RomCommand(command) => match rom_command(command) {
+                Some(name) => write!(f, "{name} ({command:#04X})"),
+                None => write!(f, "unknown ROM command ({command:#04X})"),
+            },
+            Event::FunctionCommand(command) => match function_command(command) {
+                Some(name) => write!(f, "{name} ({command:#04X})"),
+                None => write!(f, "unknown function command ({command:#04X})"),
+            },
+            Event::Write(byte) => write!(f, "write {byte:#04X}"),
+            Event::Read(byte) => write!(f, "read {byte:#04X}"),
+            Event::Bits { read, value, len } => {
+                let direction = if read { "read" } else { "write" };
+                write!(
+                    f,
+                    "{direction} {len} bits {value:#0width$b}",
+                    width = len as usize + 2
+                )
+            }
+            Event::Search { rom } => write!(f, "search {rom:016X}"),
+        }
+    }
+}
+
+fn rom_command(command: u8) -> Option<&'static str> {
+    Some(match command {
+        COMMAND_ROM_READ => "read ROM",
+        COMMAND_ROM_MATCH => "match ROM",
+        COMMAND_ROM_SKIP => "skip ROM",
+        COMMAND_ROM_SEARCH => "search ROM",
+        COMMAND_ALARM_SEARCH => "alarm search",
+        COMMAND_ROM_OVERDRIVE_MATCH => "overdrive match ROM",
+        COMMAND_ROM_OVERDRIVE_SKIP => "overdrive skip ROM",
+        _ => return None,
+    })
+}
+
+fn function_command(command: u8) -> Option<&'static str> {
+    Some(match command {
+        COMMAND_MEMORY_CONVERT => "convert T",
+        COMMAND_MEMORY_RECALL => "recall E2",
+        COMMAND_MEMORY_POWER_SUPPLY_READ => "read power supply",
+        COMMAND_MEMORY_SCRATCHPAD_COPY => "copy scratchpad",
+        COMMAND_MEMORY_SCRATCHPAD_READ => "read scratchpad",
+        COMMAND_MEMORY_SCRATCHPAD_WRITE => "write scratchpad",
+        _ => return None,
+    })
+}
+
+/// Transaction phase
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Phase {
+    /// Before the first reset.
+    Idle,
+    RomCommand,
+    /// The bytes of the ROM left.
+    Rom(u8),
+    FunctionCommand,
+    Data,
+    Search,
+}
+
+/// Bits of the byte being decoded
+#[derive(Clone, Copy, Debug, Default)]
+struct Bits {
+    time: u64,
+    read: bool,
+    value: u8,
+    len: u8,
+}
+
+impl Bits {
+    fn flush(&mut self) -> Option<Traced> {
+        let len = core::mem::take(&mut self.len);
+        (len != 0).then_some(Traced {
+            time: self.time,
+            event: Event::Bits {
+                read: self.read,
+                value: self.value,
+                len,
+            },
+        })
+    }
+}
+
+/// Events
+///
+/// An iterator decoding the records into events.
+pub struct Events<I: Iterator<Item = Record>> {
+    records: core::iter::Peekable<I>,
+    phase: Phase,
+    bits: Bits,
+    /// Slots of the search pass and the written directions.
+    search: (u32, u64),
+    /// Event decoded along with the bits flushed before it.
+    pending: Option<Traced>,
+}
+
+/// Slot: low time and sampled level.
+enum Slot {
+    Reset { presence: bool },
+    Bit { read: Option<bool>, low: u64 },
+}
+
+impl<I: Iterator<Item = Record>> Events<I> {
+    /// Returns the next slot and its time.
+    fn slot(&mut self) -> Option<(u64, Slot)> {
+        let low = loop {
+            if let Record::Low(time) = self.records.next()? {
+                break time;
+            }
+        };
+        let high = loop {
+            match self.records.next()? {
+                Record::High(time) => break time,
+                _ => continue,
+            }
+        };
+        let duration = high - low;
+        let (from, window) = if duration >= RESET_NS {
+            (high, PRESENCE_SAMPLE_NS)
+        } else {
+            (low, SLOT_SAMPLE_NS)
+        };
+        let mut sample = None;
+        while let Some(&Record::Sample(time, level)) = self.records.peek() {
+            self.records.next();
+            if sample.is_none() && time - from <= window {
+                sample = Some(level);
+            }
+        }
+        let slot = if duration >= RESET_NS {
+            Slot::Reset {
+                presence: sample == Some(false),
+            }
+        } else {
+            Slot::Bit {
+                read: sample,
+                low: duration,
+            }
+        };
+        Some((low, slot))
+    }
+
+    /// Decodes a written or read byte in the current phase.
+    fn byte(&mut self, read: bool, byte: u8) -> Event {
+        match (self.phase, read) {
+            (Phase::RomCommand, false) => {
+                self.phase = match byte {
+                    COMMAND_ROM_MATCH | COMMAND_ROM_OVERDRIVE_MATCH => Phase::Rom(8),
+                    COMMAND_ROM_SKIP | COMMAND_ROM_OVERDRIVE_SKIP => Phase::FunctionCommand,
+                    COMMAND_ROM_SEARCH | COMMAND_ALARM_SEARCH => {
+                        self.search = (0, 0);
+                        Phase::Search
+                    }
+                    _ => Phase::Data,
+                };
+                Event::RomCommand(byte)
+            }
+            (Phase::Rom(bytes), false) => {
+                self.phase = match bytes {
+                    1 => Phase::FunctionCommand,
+                    bytes => Phase::Rom(bytes - 1),
+                };
+                Event::Write(byte)
+            }
+            (Phase::FunctionCommand, false) => {
+                self.phase = Phase::Data;
+                Event::FunctionCommand(byte)
+            }
+            (_, true) => Event::Read(byte),
+            (_, false) => Event::Write(byte),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Record>> Iterator for Events<I> {
+    type Item = Traced;
+
+    fn next(&mut self) -> Option<Traced> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        loop {
+            let Some((time, slot)) = self.slot() else {
+                return self.bits.flush();
+            };
+            match slot {
+                Slot::Reset { presence } => {
+                    self.phase = Phase::RomCommand;
+                    let reset = Traced {
+                        time,
+                        event: Event::Reset { presence },
+                    };
+                    // The bits left before the reset come first.
+                    if let Some(bits) = self.bits.flush() {
+                        self.pending = Some(reset);
+                        return Some(bits);
+                    }
+                    return Some(reset);
+                }
+                Slot::Bit { read, low } if self.phase == Phase::Search => {
+                    // Triplets: bit, complement, direction.
+                    let (slots, rom) = &mut self.search;
+                    if *slots % 3 == 2 && read.is_none() && low < WRITE_ONE_NS {
+                        *rom |= 1 << (*slots / 3);
+                    }
+                    *slots += 1;
+                    if *slots == 3 * u64::BITS {
+                        self.phase = Phase::Data;
+                        return Some(Traced {
+                            time,
+                            event: Event::Search { rom: *rom },
+                        });
+                    }
+                }
+                Slot::Bit { read, low } => {
+                    let (is_read, bit) = match read {
+                        Some(level) => (true, level),
+                        None => (false, low < WRITE_ONE_NS),
+                    };
+                    let mut flushed = None;
+                    if self.bits.len != 0 && self.bits.read != is_read {
+                        flushed = self.bits.flush();
+                    }
+                    if self.bits.len == 0 {
+                        self.bits = Bits {
+                            time,
+                            read: is_read,
+                            value: 0,
+                            len: 0,
+                        };
+                    }
+                    self.bits.value |= (bit as u8) << self.bits.len;
+                    self.bits.len += 1;
+                    if flushed.is_some() {
+                        return flushed;
+                    }
+                    if self.bits.len == 8 {
+                        let Bits {
+                            time, read, value, ..
+                        } = self.bits;
+                        self.bits.len = 0;
+                        let event = self.byte(read, value);
+                        return Some(Traced { time, event });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod test {
+    use super::*;
+    use crate::{
+        simulator::{testing::device, Bus, Delay, Pin},
+        Driver, Ds18b20, RomCommands,
+    };
+
+    extern crate std;
+    use std::{string::String, vec::Vec};
+
+    #[test]
+    fn events() {
+        let device = device(0x6F);
+        let bus = RefCell::new(Bus::with_devices(&[device]));
+        let recorder = RefCell::new(Recorder::<1024>::new());
+        let pin = TracePin::new(Pin::new(&bus), &recorder);
+        let delay = TraceDelay::new(Delay::new(&bus), &recorder);
+        let mut driver = Driver::new(pin, delay).unwrap();
+        let rom = driver.devices().next().unwrap().unwrap();
+        recorder.borrow_mut().clear();
+        let ds18b20 = Ds18b20::new(rom).unwrap();
+        ds18b20.read_scratchpad(&mut driver).unwrap();
+        ds18b20.read_power_supply(&mut driver).unwrap();
+
+        let recorder = recorder.borrow();
+        let events: Vec<_> = recorder.events().map(|traced| traced.event).collect();
+        let mut expected = std::vec![
+            Event::Reset { presence: true },
+            Event::RomCommand(COMMAND_ROM_MATCH),
+        ];
+        expected.extend(<[u8; 8]>::from(rom).map(Event::Write));
+        expected.push(Event::FunctionCommand(COMMAND_MEMORY_SCRATCHPAD_READ));
+        expected.extend(device.scratchpad().map(Event::Read));
+        expected.extend([
+            Event::Reset { presence: true },
+            Event::RomCommand(COMMAND_ROM_MATCH),
+        ]);
+        expected.extend(<[u8; 8]>::from(rom).map(Event::Write));
+        expected.extend([
+            Event::FunctionCommand(COMMAND_MEMORY_POWER_SUPPLY_READ),
+            Event::Bits {
+                read: true,
+                value: 1,
+                len: 1,
+            },
+        ]);
+        assert_eq!(expected, events);
+    }
+
+    #[test]
+    fn search() {
+        let device = device(0x6F);
+        let bus = RefCell::new(Bus::with_devices(&[device]));
+        let recorder = RefCell::new(Recorder::<1024>::new());
+        let pin = TracePin::new(Pin::new(&bus), &recorder);
+        let delay = TraceDelay::new(Delay::new(&bus), &recorder);
+        let mut driver = Driver::new(pin, delay).unwrap();
+        driver.search_rom(&mut Default::default()).unwrap();
+
+        let mut log = Vec::new();
+        recorder.borrow().write_log(&mut log).unwrap();
+        let log = String::from_utf8(log).unwrap();
+        let lines: Vec<_> = log
+            .lines()
+            .map(|line| line.split_once(" µs  ").unwrap().1)
+            .collect();
+        let rom = std::format!("search {:016X}", u64::from(*device.rom()));
+        assert_eq!(
+            std::vec!["reset, presence", "search ROM (0xF0)", &rom],
+            lines
+        );
+        let mut vcd = Vec::new();
+        recorder.borrow().write_vcd(&mut vcd).unwrap();
+        assert!(String::from_utf8(vcd)
+            .unwrap()
+            .contains("$var wire 1 m master $end"));
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let mut recorder = Recorder::<2>::new();
+        for time in 0..3 {
+            recorder.push(Record::Low(time));
+        }
+        assert_eq!(
+            std::vec![Record::Low(1), Record::Low(2)],
+            recorder.records().collect::<Vec<_>>()
+        );
+    }
+}
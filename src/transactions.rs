@@ -1,30 +1,65 @@
-use crate::Rom;
+use crate::{Ds18b20, Ds18b20Error, Error, OneWireBus, RetryPolicy, Rom, RomCommands, Temperature};
+use core::slice::Iter;
 
-/// Load config from EEPROM to scratchpad.
+/// Transaction
 ///
-/// If `rom` is `None` - for all devices simultaneously.
+/// Every memory function command is preceded by the initialization sequence
+/// and a ROM command. If `rom` is `None` - for all devices simultaneously
+/// (skip ROM), otherwise for the matching device only (match ROM).
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Transaction {
     pub rom: Option<Rom>,
 }
 
-// impl Command for MemoryRecall {
-//     type Output = Result<()>;
+impl Transaction {
+    /// Sends the initialization sequence and the ROM command, after which the
+    /// selected devices wait for a memory function command.
+    pub fn begin<B: OneWireBus>(&self, bus: &mut B) -> Result<(), Error<B::Error>> {
+        if !bus.initialization()? {
+            return Err(bus.report(Ds18b20Error::NoAttachedDevices.into()));
+        }
+        match self.rom {
+            Some(rom) => bus.match_rom(rom),
+            None => bus.skip_rom(),
+        }
+    }
+}
 
-//     fn execute(&self, driver: &mut OneWireDriver<impl Pin, impl DelayNs>) -> Self::Output {
-//         driver.initialization()?;
-//         match self.rom {
-//             Some(rom) => driver.run(MatchRom { rom })?,
-//             None => driver.run(SkipRom)?,
-//         }
-//         driver.write_byte(COMMAND_MEMORY_RECALL)?;
-//         // wait for the recall to finish (up to 10ms)
-//         let max_retries = (10000 / READ_SLOT_DURATION_MICROS) + 1;
-//         for _ in 0..max_retries {
-//             if driver.read_bit()? == true {
-//                 return Ok(());
-//             }
-//         }
-//         Err(Error::Timeout)
-//     }
-// }
+/// Temperatures
+///
+/// An iterator over the temperatures read from the devices after a
+/// simultaneous conversion.
+pub struct Temperatures<'a, B> {
+    bus: &'a mut B,
+    devices: Iter<'a, Ds18b20>,
+    /// Retries of each read.
+    policy: RetryPolicy,
+}
+
+impl<'a, B> Temperatures<'a, B> {
+    pub(crate) fn new(bus: &'a mut B, devices: &'a [Ds18b20], policy: RetryPolicy) -> Self {
+        Self {
+            bus,
+            devices: devices.iter(),
+            policy,
+        }
+    }
+}
+
+impl<B: OneWireBus> Iterator for Temperatures<'_, B> {
+    type Item = (Rom, Result<Temperature, Error<B::Error>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let device = self.devices.next()?;
+        let temperature = self
+            .policy
+            .retry(self.bus, |bus| device.read_temperature(bus))
+            .map(|retried| retried.value)
+            .map_err(Error::from);
+        Some((*device.rom(), temperature))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.devices.size_hint()
+    }
+}
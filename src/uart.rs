@@ -0,0 +1,241 @@
+//! 1-Wire master on a UART.
+//!
+//! The UART TX and RX are connected to the bus (TX through an open-drain
+//! buffer or a diode). Every frame sent is echoed back, and the devices
+//! pulling the bus low change the echo:
+//!
+//! - reset: `0xF0` at 9600 baud, the low half of the frame is the reset pulse.
+//!   An echo other than `0xF0` means a presence pulse;
+//! - bit: `0xFF` (write 1 or read) or `0x00` (write 0) at 115200 baud, the
+//!   start bit is the write 1 low time. An echo of `0xFF` reads as 1.
+
+use crate::{Ds18b20Error, Error, OneWireBus};
+use embedded_hal::delay::DelayNs;
+use embedded_io::{ErrorType, Read, ReadExactError, Write};
+
+const RESET_BAUD_RATE: u32 = 9_600;
+const DATA_BAUD_RATE: u32 = 115_200;
+
+const RESET: u8 = 0xF0;
+const ONE: u8 = 0xFF;
+const ZERO: u8 = 0x00;
+
+/// Baud rate
+///
+/// The hook to switch the UART between the reset and the data baud rates.
+pub trait BaudRate: ErrorType {
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), Self::Error>;
+}
+
+/// UART 1-Wire master
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Uart<T, U> {
+    uart: T,
+    delay: U,
+    parasite_power: bool,
+}
+
+impl<T, U> Uart<T, U> {
+    pub fn new(uart: T, delay: U) -> Self {
+        Self {
+            uart,
+            delay,
+            parasite_power: false,
+        }
+    }
+
+    pub fn into_inner(self) -> (T, U) {
+        (self.uart, self.delay)
+    }
+}
+
+impl<T: Read + Write + BaudRate, U> Uart<T, U> {
+    /// Sends the frames and reads their echoes back into the same buffer.
+    fn transfer(&mut self, frames: &mut [u8]) -> Result<(), Error<T::Error>> {
+        self.uart.write_all(frames).map_err(Error::Bus)?;
+        self.uart.flush().map_err(Error::Bus)?;
+        self.uart.read_exact(frames).map_err(|error| match error {
+            // No echo, the UART isn't connected to the bus.
+            ReadExactError::UnexpectedEof => Error::Ds18b20(Ds18b20Error::Timeout),
+            ReadExactError::Other(error) => Error::Bus(error),
+        })
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), Error<T::Error>> {
+        self.uart.set_baud_rate(baud_rate).map_err(Error::Bus)
+    }
+}
+
+impl<T: Read + Write + BaudRate, U: DelayNs> OneWireBus for Uart<T, U> {
+    type Error = T::Error;
+
+    fn initialization(&mut self) -> Result<bool, Error<T::Error>> {
+        self.set_baud_rate(RESET_BAUD_RATE)?;
+        let mut frame = [RESET];
+        let result = self.transfer(&mut frame);
+        self.set_baud_rate(DATA_BAUD_RATE)?;
+        result?;
+        match frame[0] {
+            RESET => Ok(false),
+            // The bus is held low.
+            ZERO => Err(Ds18b20Error::BusNotHigh)?,
+            _ => Ok(true),
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error<T::Error>> {
+        let mut frame = [ONE];
+        self.transfer(&mut frame)?;
+        Ok(frame[0] == ONE)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error<T::Error>> {
+        let mut frame = [if bit { ONE } else { ZERO }];
+        self.transfer(&mut frame)
+    }
+
+    fn delay(&mut self, ns: u32) {
+        self.delay.delay_ns(ns);
+    }
+
+    fn parasite_power(&self) -> bool {
+        self.parasite_power
+    }
+
+    fn set_parasite_power(&mut self, parasite_power: bool) {
+        self.parasite_power = parasite_power;
+    }
+
+    /// Sends the 8 read slots at once.
+    fn read_byte(&mut self) -> Result<u8, Error<T::Error>> {
+        let mut frames = [ONE; 8];
+        self.transfer(&mut frames)?;
+        Ok(frames
+            .iter()
+            .rev()
+            .fold(0, |byte, &frame| byte << 1 | (frame == ONE) as u8))
+    }
+
+    /// Sends the 8 write slots at once.
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error<T::Error>> {
+        let mut frames = [ZERO; 8];
+        for (index, frame) in frames.iter_mut().enumerate() {
+            if byte >> index & 0x01 == 0x01 {
+                *frame = ONE;
+            }
+        }
+        self.transfer(&mut frames)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Rom, RomCommands, Speed};
+    use core::convert::Infallible;
+
+    /// Echoes the frames, a device responds with the given bits.
+    struct Echo {
+        baud_rate: u32,
+        presence: bool,
+        bits: u8,
+        echo: [u8; 8],
+        len: usize,
+        /// All frames written.
+        written: usize,
+    }
+
+    impl ErrorType for Echo {
+        type Error = Infallible;
+    }
+
+    impl Write for Echo {
+        fn write(&mut self, frames: &[u8]) -> Result<usize, Infallible> {
+            for &frame in frames {
+                self.echo[self.len] = match (self.baud_rate, frame) {
+                    (RESET_BAUD_RATE, RESET) if self.presence => 0xE0,
+                    (DATA_BAUD_RATE, ONE) => {
+                        let bit = self.bits & 0x01;
+                        self.bits >>= 1;
+                        if bit == 0x01 {
+                            ONE
+                        } else {
+                            0xFE
+                        }
+                    }
+                    (_, frame) => frame,
+                };
+                self.len += 1;
+                self.written += 1;
+            }
+            Ok(frames.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl Read for Echo {
+        fn read(&mut self, frames: &mut [u8]) -> Result<usize, Infallible> {
+            let len = frames.len().min(self.len);
+            frames[..len].copy_from_slice(&self.echo[..len]);
+            self.echo.copy_within(len.., 0);
+            self.len -= len;
+            Ok(len)
+        }
+    }
+
+    impl BaudRate for Echo {
+        fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), Infallible> {
+            self.baud_rate = baud_rate;
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _: u32) {}
+    }
+
+    fn uart(presence: bool, bits: u8) -> Uart<Echo, NoDelay> {
+        let echo = Echo {
+            baud_rate: DATA_BAUD_RATE,
+            presence,
+            bits,
+            echo: [0; 8],
+            len: 0,
+            written: 0,
+        };
+        Uart::new(echo, NoDelay)
+    }
+
+    #[test]
+    fn initialization() {
+        assert_eq!(Ok(true), uart(true, 0).initialization());
+        assert_eq!(Ok(false), uart(false, 0).initialization());
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(Ok(0x28), uart(true, 0x28).read_byte());
+        assert_eq!(Ok(()), uart(true, 0).write_byte(0xCC));
+        let mut uart = uart(true, 0b10);
+        assert_eq!(Ok(false), uart.read_bit());
+        assert_eq!(Ok(true), uart.read_bit());
+    }
+
+    #[test]
+    fn overdrive() {
+        let mut uart = uart(true, 0);
+        let unsupported = Err(Error::Ds18b20(Ds18b20Error::UnsupportedSpeed {
+            speed: Speed::Overdrive,
+        }));
+        assert_eq!(unsupported, uart.overdrive_skip_rom());
+        assert_eq!(unsupported, uart.overdrive_match_rom(Rom::default()));
+        // The devices are left at standard speed.
+        assert_eq!(0, uart.uart.written);
+        assert_eq!(Speed::Standard, uart.speed());
+    }
+}
@@ -0,0 +1,289 @@
+//! DS18B20s behind the Linux kernel `w1` subsystem.
+//!
+//! The `w1-gpio` (or any other master) driver runs the bus and the `w1_therm`
+//! driver exposes every DS18B20 as a `28-xxxxxxxxxxxx` directory in sysfs:
+//!
+//! - `w1_slave`: reading converts the temperature and returns the scratchpad;
+//! - `resolution`: 9 through 12 bits;
+//! - `alarms`: `TL TH` temperature triggers;
+//! - `eeprom`: `save` copies the scratchpad to EEPROM, `restore` recalls it;
+//! - `ext_power`: `0` parasite-powered, `1` externally powered;
+//! - `conv_time`: conversion time in ms.
+
+use crate::{
+    crc8::calculate,
+    scratchpad::{Resolution, Triggers},
+    Ds18b20Error, Error, PowerSupply, Rom, Scratchpad, Temperature, FAMILY_CODE,
+};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    string::{String, ToString},
+    time::Duration,
+    vec::Vec,
+};
+
+/// The default sysfs directory of the 1-Wire devices.
+pub const DEVICES: &str = "/sys/bus/w1/devices";
+
+/// Linux `w1` subsystem
+#[derive(Clone, Debug)]
+pub struct W1 {
+    root: PathBuf,
+}
+
+impl W1 {
+    /// Uses the given directory instead of [`DEVICES`].
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the ROMs of the DS18B20s, sorted.
+    ///
+    /// The devices are listed by the `w1_master_slaves` files of the bus
+    /// masters, or by the device directories if there are no masters.
+    pub fn devices(&self) -> Result<Vec<Rom>, Error<io::Error>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.root).map_err(Error::Bus)? {
+            let name = entry.map_err(Error::Bus)?.file_name();
+            entries.push(name.to_string_lossy().into_owned());
+        }
+        let masters: Vec<_> = entries
+            .iter()
+            .filter(|name| name.starts_with("w1_bus_master"))
+            .collect();
+        // Directories of removed devices may linger until the master lists
+        // its slaves again.
+        let mut names = Vec::new();
+        for master in &masters {
+            let slaves = read(&self.root.join(master).join("w1_master_slaves"))?;
+            names.extend(slaves.lines().map(ToString::to_string));
+        }
+        if masters.is_empty() {
+            names = entries;
+        }
+        let mut roms: Vec<_> = names
+            .iter()
+            .filter_map(|name| parse_rom(name))
+            .filter(|rom| rom.family_code == FAMILY_CODE)
+            .collect();
+        roms.sort_by_key(|&rom| u64::from(rom));
+        roms.dedup();
+        Ok(roms)
+    }
+
+    /// Returns the device with the given ROM.
+    pub fn device(&self, rom: Rom) -> W1Device {
+        W1Device {
+            path: self.root.join(rom_name(&rom)),
+            rom,
+        }
+    }
+}
+
+impl Default for W1 {
+    fn default() -> Self {
+        Self::with_root(DEVICES)
+    }
+}
+
+/// DS18B20 behind the `w1_therm` driver
+#[derive(Clone, Debug)]
+pub struct W1Device {
+    path: PathBuf,
+    rom: Rom,
+}
+
+impl W1Device {
+    /// Returns the device rom
+    pub fn rom(&self) -> &Rom {
+        &self.rom
+    }
+
+    /// Converts the temperature and reads the scratchpad.
+    ///
+    /// The first line of `w1_slave` holds the 9 scratchpad bytes, the CRC is
+    /// checked again.
+    pub fn read_scratchpad(&self) -> Result<Scratchpad, Error<io::Error>> {
+        let w1_slave = read(&self.path.join("w1_slave"))?;
+        let mut bytes = [0; 9];
+        let mut values = w1_slave.split_whitespace();
+        for byte in &mut bytes {
+            *byte = values
+                .next()
+                .and_then(|value| u8::from_str_radix(value, 16).ok())
+                .ok_or(Ds18b20Error::UnexpectedResponse)?;
+        }
+        Ok(bytes.try_into()?)
+    }
+
+    /// Converts and reads the temperature, see
+    /// [`Scratchpad::measured_temperature`].
+    pub fn read_temperature(&self) -> Result<Temperature, Error<io::Error>> {
+        Ok(self.read_scratchpad()?.measured_temperature()?)
+    }
+
+    pub fn resolution(&self) -> Result<Resolution, Error<io::Error>> {
+        match parse::<u8>(&read(&self.path.join("resolution"))?)? {
+            9 => Ok(Resolution::Nine),
+            10 => Ok(Resolution::Ten),
+            11 => Ok(Resolution::Eleven),
+            12 => Ok(Resolution::Twelve),
+            _ => Err(Ds18b20Error::UnexpectedResponse)?,
+        }
+    }
+
+    pub fn set_resolution(&self, resolution: Resolution) -> Result<(), Error<io::Error>> {
+        let bits = match resolution {
+            Resolution::Nine => "9",
+            Resolution::Ten => "10",
+            Resolution::Eleven => "11",
+            Resolution::Twelve => "12",
+        };
+        write(&self.path.join("resolution"), bits)
+    }
+
+    /// Reads the temperature triggers.
+    pub fn alarms(&self) -> Result<Triggers, Error<io::Error>> {
+        let alarms = read(&self.path.join("alarms"))?;
+        let mut values = alarms.split_whitespace();
+        let low = parse(values.next().unwrap_or_default())?;
+        let high = parse(values.next().unwrap_or_default())?;
+        Ok(Triggers { high, low })
+    }
+
+    /// Writes the temperature triggers.
+    pub fn set_alarms(&self, triggers: Triggers) -> Result<(), Error<io::Error>> {
+        let alarms = std::format!("{} {}", triggers.low, triggers.high);
+        write(&self.path.join("alarms"), &alarms)
+    }
+
+    /// Saves the temperature triggers and the resolution to EEPROM.
+    pub fn save_to_eeprom(&self) -> Result<(), Error<io::Error>> {
+        write(&self.path.join("eeprom"), "save")
+    }
+
+    /// Loads the temperature triggers and the resolution from EEPROM.
+    pub fn recall_from_eeprom(&self) -> Result<(), Error<io::Error>> {
+        write(&self.path.join("eeprom"), "restore")
+    }
+
+    pub fn power_supply(&self) -> Result<PowerSupply, Error<io::Error>> {
+        match parse::<i32>(&read(&self.path.join("ext_power"))?)? {
+            0 => Ok(PowerSupply::Parasite),
+            1 => Ok(PowerSupply::External),
+            _ => Err(Ds18b20Error::UnexpectedResponse)?,
+        }
+    }
+
+    pub fn conversion_time(&self) -> Result<Duration, Error<io::Error>> {
+        let ms = parse(&read(&self.path.join("conv_time"))?)?;
+        Ok(Duration::from_millis(ms))
+    }
+
+    /// Sets the conversion time the driver waits for, `0` restores the
+    /// default.
+    pub fn set_conversion_time(&self, conversion_time: Duration) -> Result<(), Error<io::Error>> {
+        let ms = conversion_time.as_millis().to_string();
+        write(&self.path.join("conv_time"), &ms)
+    }
+}
+
+/// Parses the device directory name, e.g. `28-0316a2791dff`.
+///
+/// The serial number is written most significant byte first, the CRC is not
+/// part of the name and is calculated.
+fn parse_rom(name: &str) -> Option<Rom> {
+    let (family_code, serial_number) = name.split_once('-')?;
+    if family_code.len() != 2 || serial_number.len() != 12 {
+        return None;
+    }
+    let family_code = u8::from_str_radix(family_code, 16).ok()?;
+    let serial_number = u64::from_str_radix(serial_number, 16).ok()?;
+    let mut bytes = [0; 8];
+    bytes[0] = family_code;
+    bytes[1..7].copy_from_slice(&serial_number.to_le_bytes()[..6]);
+    bytes[7] = calculate(&bytes[..7]);
+    bytes.try_into().ok()
+}
+
+fn rom_name(rom: &Rom) -> String {
+    let mut serial_number = [0; 8];
+    serial_number[..6].copy_from_slice(&rom.serial_number);
+    std::format!(
+        "{:02x}-{:012x}",
+        rom.family_code,
+        u64::from_le_bytes(serial_number)
+    )
+}
+
+fn parse<T: core::str::FromStr>(value: &str) -> Result<T, Error<io::Error>> {
+    Ok(value
+        .trim()
+        .parse()
+        .map_err(|_| Ds18b20Error::UnexpectedResponse)?)
+}
+
+fn read(path: &Path) -> Result<String, Error<io::Error>> {
+    fs::read_to_string(path).map_err(|error| match error.kind() {
+        // The device has left the bus.
+        ErrorKind::NotFound => Error::Ds18b20(Ds18b20Error::NoAttachedDevices),
+        _ => Error::Bus(error),
+    })
+}
+
+fn write(path: &Path, value: &str) -> Result<(), Error<io::Error>> {
+    fs::write(path, value).map_err(Error::Bus)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const W1_SLAVE: &str = "\
+72 01 4b 46 7f ff 0e 10 57 : crc=57 YES
+72 01 4b 46 7f ff 0e 10 57 t=23125
+";
+
+    #[test]
+    fn sysfs() {
+        let root = std::env::temp_dir().join(std::format!("ds18b20-w1-{}", std::process::id()));
+        let device = root.join("28-0316a2791dff");
+        let master = root.join("w1_bus_master1");
+        fs::create_dir_all(&device).unwrap();
+        fs::create_dir_all(&master).unwrap();
+        // Left the bus, not listed by the master anymore.
+        fs::create_dir_all(root.join("28-0316a2791e00")).unwrap();
+        fs::write(
+            master.join("w1_master_slaves"),
+            "28-0316a2791dff\n10-000802d4f8a1\n",
+        )
+        .unwrap();
+        fs::write(device.join("w1_slave"), W1_SLAVE).unwrap();
+        fs::write(device.join("resolution"), "12\n").unwrap();
+        fs::write(device.join("alarms"), "70 75\n").unwrap();
+        fs::write(device.join("ext_power"), "0\n").unwrap();
+
+        let w1 = W1::with_root(&root);
+        let roms = w1.devices().unwrap();
+        assert_eq!(1, roms.len());
+        assert_eq!([0xFF, 0x1D, 0x79, 0xA2, 0x16, 0x03], roms[0].serial_number);
+        let device = w1.device(roms[0]);
+        let scratchpad = device.read_scratchpad().unwrap();
+        assert_eq!(Temperature::from_raw(0x0172), scratchpad.temperature());
+        assert_eq!(23_125, scratchpad.temperature().to_millidegrees_celsius());
+        assert_eq!(Resolution::Twelve, device.resolution().unwrap());
+        assert_eq!(PowerSupply::Parasite, device.power_supply().unwrap());
+        let triggers = device.alarms().unwrap();
+        assert_eq!((75, 70), (triggers.high, triggers.low));
+        device.set_resolution(Resolution::Nine).unwrap();
+        device.set_alarms(Triggers { high: 30, low: -10 }).unwrap();
+        assert_eq!(Resolution::Nine, device.resolution().unwrap());
+        assert_eq!(
+            "-10 30",
+            fs::read_to_string(root.join("28-0316a2791dff/alarms")).unwrap()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}